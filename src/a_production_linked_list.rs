@@ -1,14 +1,21 @@
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::mem::MaybeUninit;
 use std::ops::Not;
+use std::ptr;
 use std::ptr::NonNull;
+use std::rc::Rc;
+
+use crate::invariant::{has_cycle, InvariantError};
 
 type Link<T> = Option<NonNull<Node<T>>>;
 
-struct Node<T> {
+/// A list node. Only visible so `NodeAllocator`s can name it.
+pub struct Node<T> {
     /// front表示前一个节点
     front: Link<T>,
     /// front表示后一个节点
@@ -16,17 +23,168 @@ struct Node<T> {
     elem: T,
 }
 
-pub struct LinkedList<T> {
+pub struct LinkedList<T, A: NodeAllocator<T> = Global> {
     /// front表示最前一个节点
     front: Link<T>,
     /// back表示最后一个节点
     back: Link<T>,
     len: usize,
+    alloc: A,
+}
+
+/// Where a `LinkedList` gets the memory for its nodes.
+///
+/// # Safety
+///
+/// `allocate` must hand out memory that is valid for a `Node<T>` and that
+/// no one else uses until it comes back through `deallocate`. Lists move
+/// nodes between each other whenever `shares_nodes_with` says so, so any
+/// allocator sharing nodes with this one (clones included) must accept them.
+pub unsafe trait NodeAllocator<T>: Clone {
+    /// Memory for one node, not yet initialized.
+    fn allocate(&self) -> NonNull<Node<T>>;
+
+    /// Take back memory whose node has already been moved out.
+    ///
+    /// # Safety
+    ///
+    /// `node` must come from `allocate` on an allocator that shares nodes
+    /// with this one and must not be used again.
+    unsafe fn deallocate(&self, node: NonNull<Node<T>>);
+
+    /// Whether nodes allocated by `other` may be deallocated by `self`.
+    fn shares_nodes_with(&self, other: &Self) -> bool;
+}
+
+/// The global allocator, one `Box` per node.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Global;
+
+unsafe impl<T> NodeAllocator<T> for Global {
+    fn allocate(&self) -> NonNull<Node<T>> {
+        let slot = Box::into_raw(Box::new(MaybeUninit::<Node<T>>::uninit()));
+        unsafe { NonNull::new_unchecked(slot.cast()) }
+    }
+
+    unsafe fn deallocate(&self, node: NonNull<Node<T>>) {
+        drop(Box::from_raw(node.as_ptr().cast::<MaybeUninit<Node<T>>>()));
+    }
+
+    fn shares_nodes_with(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// A slab of node slots. Freed nodes go on a free list and are handed out
+/// again before any new memory is allocated, and the slab only grows, in
+/// chunks that double in size. Clones share the same slab, which lives until
+/// the last list using it is gone.
+pub struct NodePool<T> {
+    slab: Rc<RefCell<Slab<T>>>,
 }
 
-impl<T: Clone> Clone for LinkedList<T> {
+struct Slab<T> {
+    free: Vec<NonNull<Node<T>>>,
+    chunks: Vec<*mut [MaybeUninit<Node<T>>]>,
+    capacity: usize,
+}
+
+impl<T> NodePool<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut slab = Slab {
+            free: Vec::new(),
+            chunks: Vec::new(),
+            capacity: 0,
+        };
+        slab.grow(capacity);
+        NodePool {
+            slab: Rc::new(RefCell::new(slab)),
+        }
+    }
+
+    /// How many nodes the pool has room for, in use or not.
+    pub fn capacity(&self) -> usize {
+        self.slab.borrow().capacity
+    }
+
+    /// How many nodes can be allocated before the pool has to grow.
+    pub fn available(&self) -> usize {
+        self.slab.borrow().free.len()
+    }
+}
+
+impl<T> Slab<T> {
+    fn grow(&mut self, additional: usize) {
+        if additional == 0 {
+            return;
+        }
+        let chunk: Box<[MaybeUninit<Node<T>>]> =
+            (0..additional).map(|_| MaybeUninit::uninit()).collect();
+        let chunk = Box::into_raw(chunk);
+        let first = chunk.cast::<Node<T>>();
+        // Push in reverse so the lowest addresses are handed out first
+        for i in (0..additional).rev() {
+            self.free
+                .push(unsafe { NonNull::new_unchecked(first.add(i)) });
+        }
+        self.chunks.push(chunk);
+        self.capacity += additional;
+    }
+}
+
+impl<T> Drop for Slab<T> {
+    fn drop(&mut self) {
+        // Every list holds on to the pool, so no node is alive anymore
+        for chunk in self.chunks.drain(..) {
+            drop(unsafe { Box::from_raw(chunk) });
+        }
+    }
+}
+
+impl<T> Clone for NodePool<T> {
+    fn clone(&self) -> Self {
+        NodePool {
+            slab: Rc::clone(&self.slab),
+        }
+    }
+}
+
+impl<T> Default for NodePool<T> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+impl<T> Debug for NodePool<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodePool")
+            .field("capacity", &self.capacity())
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+unsafe impl<T> NodeAllocator<T> for NodePool<T> {
+    fn allocate(&self) -> NonNull<Node<T>> {
+        let mut slab = self.slab.borrow_mut();
+        if slab.free.is_empty() {
+            let additional = slab.capacity.max(8);
+            slab.grow(additional);
+        }
+        slab.free.pop().unwrap()
+    }
+
+    unsafe fn deallocate(&self, node: NonNull<Node<T>>) {
+        self.slab.borrow_mut().free.push(node);
+    }
+
+    fn shares_nodes_with(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.slab, &other.slab)
+    }
+}
+impl<T: Clone, A: NodeAllocator<T>> Clone for LinkedList<T, A> {
     fn clone(&self) -> Self {
-        let mut new_list = Self::new();
+        let mut new_list = Self::new_in(self.alloc.clone());
         for item in self.iter() {
             new_list.push_back(item.clone());
         }
@@ -34,7 +192,7 @@ impl<T: Clone> Clone for LinkedList<T> {
     }
 }
 
-impl<T> Extend<T> for LinkedList<T> {
+impl<T, A: NodeAllocator<T>> Extend<T> for LinkedList<T, A> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
         for item in iter {
             self.push_back(item);
@@ -42,41 +200,41 @@ impl<T> Extend<T> for LinkedList<T> {
     }
 }
 
-impl<T> FromIterator<T> for LinkedList<T> {
+impl<T, A: NodeAllocator<T> + Default> FromIterator<T> for LinkedList<T, A> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut list = Self::new();
+        let mut list = Self::default();
         list.extend(iter);
         list
     }
 }
 
-impl<T: Debug> Debug for LinkedList<T> {
+impl<T: Debug, A: NodeAllocator<T>> Debug for LinkedList<T, A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_list().entries(self.iter()).finish()
     }
 }
 
-impl<T: PartialEq> PartialEq for LinkedList<T> {
+impl<T: PartialEq, A: NodeAllocator<T>> PartialEq for LinkedList<T, A> {
     fn eq(&self, other: &Self) -> bool {
         self.len() == other.len() && self.iter().eq(other.iter())
     }
 }
 
-impl<T: Eq> Eq for LinkedList<T> {}
+impl<T: Eq, A: NodeAllocator<T>> Eq for LinkedList<T, A> {}
 
-impl<T: PartialOrd> PartialOrd for LinkedList<T> {
+impl<T: PartialOrd, A: NodeAllocator<T>> PartialOrd for LinkedList<T, A> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         self.iter().partial_cmp(other.iter())
     }
 }
 
-impl<T: Ord> Ord for LinkedList<T> {
+impl<T: Ord, A: NodeAllocator<T>> Ord for LinkedList<T, A> {
     fn cmp(&self, other: &Self) -> Ordering {
         self.iter().cmp(other.iter())
     }
 }
 
-impl<T: Hash> Hash for LinkedList<T> {
+impl<T: Hash, A: NodeAllocator<T>> Hash for LinkedList<T, A> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.len().hash(state);
         for item in self.iter() {
@@ -85,7 +243,7 @@ impl<T: Hash> Hash for LinkedList<T> {
     }
 }
 
-impl<T: Display> Display for LinkedList<T> {
+impl<T: Display, A: NodeAllocator<T>> Display for LinkedList<T, A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "[")?;
         let iter = self.iter();
@@ -99,17 +257,36 @@ impl<T: Display> Display for LinkedList<T> {
     }
 }
 
-impl<T> Drop for LinkedList<T> {
+impl<T, A: NodeAllocator<T>> Drop for LinkedList<T, A> {
     fn drop(&mut self) {
         while self.pop_front().is_some() {}
     }
 }
 
-impl<T> Iterator for LinkedList<T> {
+impl<T, A: NodeAllocator<T>> IntoIterator for LinkedList<T, A> {
     type Item = T;
+    type IntoIter = IntoIter<T, A>;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.pop_front()
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T, A: NodeAllocator<T>> IntoIterator for &'a LinkedList<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: NodeAllocator<T>> IntoIterator for &'a mut LinkedList<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
@@ -117,12 +294,35 @@ impl<T> LinkedList<T> {
     pub fn new() -> Self {
         Self::default()
     }
+}
+
+impl<T> LinkedList<T, NodePool<T>> {
+    /// An empty list whose nodes come from a fresh pool with room for
+    /// `capacity` of them. Lists split off from it share the pool.
+    pub fn with_node_pool(capacity: usize) -> Self {
+        Self::new_in(NodePool::with_capacity(capacity))
+    }
+}
+
+impl<T, A: NodeAllocator<T>> LinkedList<T, A> {
+    pub fn new_in(alloc: A) -> Self {
+        LinkedList {
+            front: None,
+            back: None,
+            len: 0,
+            alloc,
+        }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
 
     pub fn len(&self) -> usize {
         self.len
     }
 
-    pub fn cursor_mut(&mut self) -> CursorMut<T> {
+    pub fn cursor_mut(&mut self) -> CursorMut<T, A> {
         CursorMut {
             list: self,
             cur: None,
@@ -130,6 +330,38 @@ impl<T> LinkedList<T> {
         }
     }
 
+    pub fn cursor_front(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            cur: self.front,
+            list: self,
+            index: self.front.map(|_| 0),
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            cur: self.back,
+            list: self,
+            index: self.back.map(|_| self.len - 1),
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            cur: self.front,
+            index: self.front.map(|_| 0),
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            cur: self.back,
+            index: self.back.map(|_| self.len - 1),
+            list: self,
+        }
+    }
+
     pub fn iter(&self) -> Iter<T> {
         Iter {
             front: self.front,
@@ -139,7 +371,38 @@ impl<T> LinkedList<T> {
         }
     }
 
-    pub fn iter_mut(&self) -> IterMut<T> {
+    /// Needs a unique borrow, so nothing else can touch the elements while
+    /// the `IterMut` is live:
+    ///
+    /// ```compile_fail
+    /// use lists::a_production_linked_list::LinkedList;
+    ///
+    /// let mut list: LinkedList<i32> = (0..3).collect();
+    /// let shared = &list;
+    /// shared.iter_mut();
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use lists::a_production_linked_list::LinkedList;
+    ///
+    /// let mut list: LinkedList<i32> = (0..3).collect();
+    /// let mut a = list.iter_mut();
+    /// let mut b = list.iter_mut();
+    /// let x = a.next().unwrap();
+    /// let y = b.next().unwrap();
+    /// std::mem::swap(x, y);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use lists::a_production_linked_list::LinkedList;
+    ///
+    /// let mut list: LinkedList<i32> = (0..3).collect();
+    /// let mut iter = list.iter_mut();
+    /// let x = iter.next().unwrap();
+    /// let y = list.front().unwrap();
+    /// *x += *y;
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<T> {
         IterMut {
             front: self.front,
             back: self.back,
@@ -152,7 +415,18 @@ impl<T> LinkedList<T> {
         self.len == 0
     }
 
-    pub fn peek_mut(&self) -> Option<&mut T> {
+    /// Needs a unique borrow, so two of them can't alias the front:
+    ///
+    /// ```compile_fail
+    /// use lists::a_production_linked_list::LinkedList;
+    ///
+    /// let mut list: LinkedList<i32> = (0..3).collect();
+    /// let shared = &list;
+    /// let a = shared.peek_mut().unwrap();
+    /// let b = shared.peek_mut().unwrap();
+    /// std::mem::swap(a, b);
+    /// ```
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
         self.front.map(|n| unsafe { &mut (*n.as_ptr()).elem })
     }
 
@@ -162,11 +436,7 @@ impl<T> LinkedList<T> {
 
     pub fn push_front(&mut self, elem: T) {
         unsafe {
-            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
-                front: None,
-                back: None,
-                elem,
-            })));
+            let new = self.alloc_node(elem);
 
             if let Some(old) = self.front {
                 (*old.as_ptr()).front = Some(new);
@@ -177,15 +447,12 @@ impl<T> LinkedList<T> {
             self.front = Some(new);
             self.len += 1;
         }
+        self.debug_validate();
     }
 
     pub fn push_back(&mut self, elem: T) {
         unsafe {
-            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
-                front: None,
-                back: None,
-                elem,
-            })));
+            let new = self.alloc_node(elem);
 
             if let Some(old) = self.back {
                 (*old.as_ptr()).back = Some(new);
@@ -196,12 +463,13 @@ impl<T> LinkedList<T> {
             self.back = Some(new);
             self.len += 1;
         }
+        self.debug_validate();
     }
 
     pub fn pop_front(&mut self) -> Option<T> {
-        unsafe {
+        let elem = unsafe {
             self.front.map(|n| {
-                let first_node = Box::from_raw(n.as_ptr());
+                let first_node = self.free_node(n);
                 let result = first_node.elem;
                 self.front = first_node.back;
                 if let Some(new) = self.front {
@@ -212,13 +480,15 @@ impl<T> LinkedList<T> {
                 self.len -= 1;
                 result
             })
-        }
+        };
+        self.debug_validate();
+        elem
     }
 
     pub(crate) fn pop_back(&mut self) -> Option<T> {
-        unsafe {
+        let elem = unsafe {
             self.back.map(|n| {
-                let last_node = Box::from_raw(n.as_ptr());
+                let last_node = self.free_node(n);
                 let elem = last_node.elem;
                 self.back = last_node.front;
                 if let Some(new) = self.back {
@@ -229,7 +499,9 @@ impl<T> LinkedList<T> {
                 self.len -= 1;
                 elem
             })
-        }
+        };
+        self.debug_validate();
+        elem
     }
 
     pub fn front(&self) -> Option<&T> {
@@ -252,6 +524,383 @@ impl<T> LinkedList<T> {
         // Oh, look its drop again
         while let Some(_) = self.pop_front() {}
     }
+
+    pub fn get(&self, at: usize) -> Option<&T> {
+        unsafe { self.node_at(at).map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn get_mut(&mut self, at: usize) -> Option<&mut T> {
+        unsafe { self.node_at(at).map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    /// Insert `elem` so it ends up at index `at`, like `VecDeque::insert`.
+    ///
+    /// # Panics
+    ///
+    /// If `at > len`.
+    pub fn insert(&mut self, at: usize, elem: T) {
+        assert!(
+            at <= self.len,
+            "insertion index (is {at}) should be <= len (is {})",
+            self.len
+        );
+        if at == self.len {
+            self.push_back(elem);
+        } else {
+            let mut cursor = self.cursor_mut();
+            cursor.seek(at);
+            cursor.insert_before(elem);
+        }
+    }
+
+    /// Remove and return the element at index `at`, or `None` if there is
+    /// none, like `VecDeque::remove`.
+    pub fn remove(&mut self, at: usize) -> Option<T> {
+        let mut cursor = self.cursor_mut();
+        cursor.seek(at);
+        cursor.remove_current()
+    }
+
+    pub fn append(&mut self, other: &mut Self) {
+        // The ghost's "before" is the back of the list
+        self.cursor_mut().splice_before(other.take_all());
+    }
+
+    pub fn prepend(&mut self, other: &mut Self) {
+        // The ghost's "after" is the front of the list
+        self.cursor_mut().splice_after(other.take_all());
+    }
+
+    pub fn split_off(&mut self, at: usize) -> LinkedList<T, A> {
+        assert!(
+            at <= self.len,
+            "cannot split off at a nonexistent index (is {at}, len is {})",
+            self.len
+        );
+        if at == 0 {
+            return self.take_all();
+        }
+        let mut cursor = self.cursor_mut();
+        cursor.seek(at - 1);
+        cursor.split_after()
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_mut(|elem| f(elem));
+    }
+
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        self.extract_if(|elem| !f(elem)).for_each(drop);
+    }
+
+    pub fn extract_if<F>(&mut self, filter: F) -> ExtractIf<'_, T, F, A>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        ExtractIf {
+            next: self.front,
+            remaining: self.len,
+            list: self,
+            filter,
+        }
+    }
+
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    pub fn sort_by_key<K, F>(&mut self, mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        // Bottom-up merge sort over the `back` links only: each pass merges
+        // neighbouring runs of length `run` into runs of `2 * run`, until a
+        // pass does a single merge. The `front` links are rebuilt once at
+        // the end by `MergeState`'s drop, which also stitches any pending
+        // runs back together if `compare` panics halfway through a pass.
+        if self.len < 2 {
+            return;
+        }
+        let mut run = 1;
+        let mut merge = MergeState {
+            head: self.front,
+            tail: None,
+            left: None,
+            left_len: 0,
+            right: None,
+            list: self,
+        };
+        unsafe {
+            loop {
+                merge.right = merge.head.take();
+                merge.tail = None;
+                let mut merges = 0;
+
+                while let Some(left) = merge.right {
+                    merges += 1;
+
+                    // The left run is the next `run` nodes, the right run
+                    // starts straight after it.
+                    merge.left = Some(left);
+                    merge.left_len = 0;
+                    while merge.left_len < run {
+                        match merge.right {
+                            Some(node) => {
+                                merge.right = (*node.as_ptr()).back;
+                                merge.left_len += 1;
+                            }
+                            None => break,
+                        }
+                    }
+                    let mut right_len = run;
+
+                    while merge.left_len > 0 || (right_len > 0 && merge.right.is_some()) {
+                        let take_left = match (merge.left, merge.right) {
+                            _ if merge.left_len == 0 => false,
+                            _ if right_len == 0 => true,
+                            // Ties go left so equal elements keep their order
+                            (Some(l), Some(r)) => {
+                                compare(&(*l.as_ptr()).elem, &(*r.as_ptr()).elem)
+                                    != Ordering::Greater
+                            }
+                            _ => true,
+                        };
+
+                        let node = if take_left {
+                            let node = merge.left.unwrap();
+                            merge.left = (*node.as_ptr()).back;
+                            merge.left_len -= 1;
+                            node
+                        } else {
+                            let node = merge.right.unwrap();
+                            merge.right = (*node.as_ptr()).back;
+                            right_len -= 1;
+                            node
+                        };
+
+                        if let Some(tail) = merge.tail {
+                            (*tail.as_ptr()).back = Some(node);
+                        } else {
+                            merge.head = Some(node);
+                        }
+                        merge.tail = Some(node);
+                    }
+                }
+
+                if let Some(tail) = merge.tail {
+                    (*tail.as_ptr()).back = None;
+                }
+                if merges <= 1 {
+                    break;
+                }
+                run *= 2;
+            }
+        }
+        // Finishing the merge relinks the list
+        drop(merge);
+        self.debug_validate();
+    }
+
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        unsafe {
+            match (self.front, self.back) {
+                (None, None) => {}
+                (Some(_), Some(_)) => {
+                    if has_cycle(self.front, |node| (*node.as_ptr()).back) {
+                        return Err(InvariantError::Cycle);
+                    }
+                }
+                _ => return Err(InvariantError::EndsDisagree),
+            }
+
+            // No cycle, so this walk terminates
+            let mut prev = None;
+            let mut cur = self.front;
+            let mut counted = 0;
+            while let Some(node) = cur {
+                if (*node.as_ptr()).front != prev {
+                    return Err(InvariantError::BrokenBackLink { index: counted });
+                }
+                prev = cur;
+                cur = (*node.as_ptr()).back;
+                counted += 1;
+            }
+
+            if prev != self.back {
+                return Err(InvariantError::BackNotLast);
+            }
+            if counted != self.len {
+                return Err(InvariantError::LenMismatch {
+                    len: self.len,
+                    counted,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn debug_validate(&self) {
+        #[cfg(feature = "debug-invariants")]
+        if let Err(err) = self.validate() {
+            panic!("LinkedList invariant broken: {err}");
+        }
+    }
+
+    /// Detach `node` from the list, leaving its own links cleared.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be a live node of this list.
+    unsafe fn unlink_node(&mut self, node: NonNull<Node<T>>) {
+        let prev = (*node.as_ptr()).front.take();
+        let next = (*node.as_ptr()).back.take();
+
+        if let Some(prev) = prev {
+            (*prev.as_ptr()).back = next;
+        } else {
+            self.front = next;
+        }
+        if let Some(next) = next {
+            (*next.as_ptr()).front = prev;
+        } else {
+            self.back = prev;
+        }
+        self.len -= 1;
+        self.debug_validate();
+    }
+
+    /// Move a node's worth of memory out of the allocator and fill it in.
+    fn alloc_node(&self, elem: T) -> NonNull<Node<T>> {
+        let node = self.alloc.allocate();
+        unsafe {
+            ptr::write(
+                node.as_ptr(),
+                Node {
+                    front: None,
+                    back: None,
+                    elem,
+                },
+            );
+        }
+        node
+    }
+
+    /// Move a node out of its memory and give the memory back.
+    ///
+    /// # Safety
+    ///
+    /// `node` must come from `alloc_node` on this list, or on a list whose
+    /// allocator shares nodes with ours, and must no longer be linked in.
+    unsafe fn free_node(&self, node: NonNull<Node<T>>) -> Node<T> {
+        let taken = ptr::read(node.as_ptr());
+        self.alloc.deallocate(node);
+        taken
+    }
+
+    /// Swap in an empty list on the same allocator and return the old one.
+    fn take_all(&mut self) -> Self {
+        let empty = Self::new_in(self.alloc.clone());
+        std::mem::replace(self, empty)
+    }
+
+    /// Get `other` ready to be spliced into this list. Its nodes are
+    /// relinked as they are if our allocator can free them, otherwise the
+    /// elements are moved over into nodes of our own.
+    fn adopt(&self, other: Self) -> Self {
+        if self.alloc.shares_nodes_with(&other.alloc) {
+            other
+        } else {
+            let mut adopted = Self::new_in(self.alloc.clone());
+            adopted.extend(other);
+            adopted
+        }
+    }
+
+    fn node_at(&self, at: usize) -> Link<T> {
+        // Walk from whichever end is closer
+        if at >= self.len {
+            return None;
+        }
+        unsafe {
+            if at <= self.len - 1 - at {
+                let mut node = self.front;
+                for _ in 0..at {
+                    node = (*node?.as_ptr()).back;
+                }
+                node
+            } else {
+                let mut node = self.back;
+                for _ in at + 1..self.len {
+                    node = (*node?.as_ptr()).front;
+                }
+                node
+            }
+        }
+    }
+}
+
+struct MergeState<'a, T, A: NodeAllocator<T>> {
+    list: &'a mut LinkedList<T, A>,
+    /// 已经合并好的部分
+    head: Link<T>,
+    tail: Link<T>,
+    /// 左边剩余的 run, 它最后一个节点的 back 仍指向右边 run 的起点
+    left: Link<T>,
+    left_len: usize,
+    /// 右边剩余的 run, 后面接着链表里还没处理的部分
+    right: Link<T>,
+}
+
+impl<'a, T, A: NodeAllocator<T>> Drop for MergeState<'a, T, A> {
+    fn drop(&mut self) {
+        unsafe {
+            // Put whatever hasn't been merged yet back behind the merged part.
+            // After a finished sort there is nothing pending and this only
+            // terminates the list.
+            let mut rest = self.right;
+            if let (Some(left), true) = (self.left, self.left_len > 0) {
+                let mut last = left;
+                for _ in 1..self.left_len {
+                    last = (*last.as_ptr()).back.unwrap();
+                }
+                (*last.as_ptr()).back = self.right;
+                rest = Some(left);
+            }
+            if let Some(tail) = self.tail {
+                (*tail.as_ptr()).back = rest;
+            } else {
+                self.head = rest;
+            }
+
+            // Rebuild the front links from the back links
+            let mut prev = None;
+            let mut cur = self.head;
+            while let Some(node) = cur {
+                (*node.as_ptr()).front = prev;
+                prev = Some(node);
+                cur = (*node.as_ptr()).back;
+            }
+            self.list.front = self.head;
+            self.list.back = prev;
+        }
+    }
 }
 
 pub struct Iter<'a, T> {
@@ -261,11 +910,74 @@ pub struct Iter<'a, T> {
     _p: PhantomData<&'a T>,
 }
 
+/// Invariant in `T`, unlike `Iter`:
+///
+/// ```compile_fail
+/// use lists::a_production_linked_list::IterMut;
+///
+/// fn iter_mut_covariant<'i, 'a, T>(x: IterMut<'i, &'static T>) -> IterMut<'i, &'a T> {
+///     x
+/// }
+/// ```
 pub struct IterMut<'a, T> {
     front: Link<T>,
     back: Link<T>,
     len: usize,
-    _p: PhantomData<&'a T>,
+    _p: PhantomData<&'a mut T>,
+}
+
+pub struct ExtractIf<'a, T, F, A: NodeAllocator<T> = Global>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    list: &'a mut LinkedList<T, A>,
+    /// 下一个待检查的节点
+    next: Link<T>,
+    remaining: usize,
+    filter: F,
+}
+
+impl<'a, T, F, A: NodeAllocator<T>> Iterator for ExtractIf<'a, T, F, A>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Only unlink after the filter returns, so a panicking filter
+        // leaves the list whole with every element still in place.
+        while let Some(node) = self.next {
+            unsafe {
+                self.next = (*node.as_ptr()).back;
+                self.remaining -= 1;
+                if (self.filter)(&mut (*node.as_ptr()).elem) {
+                    self.list.unlink_node(node);
+                    return Some(self.list.free_node(node).elem);
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+pub struct IntoIter<T, A: NodeAllocator<T> = Global> {
+    list: LinkedList<T, A>,
+}
+
+impl<T, A: NodeAllocator<T>> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
 }
 
 impl<'a, T> Iterator for Iter<'a, T> {
@@ -309,13 +1021,9 @@ impl<'a, T> Iterator for IterMut<'a, T> {
     }
 }
 
-impl<T> DoubleEndedIterator for LinkedList<T> {
+impl<T, A: NodeAllocator<T>> DoubleEndedIterator for IntoIter<T, A> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        if self.len == 0 {
-            None
-        } else {
-            self.pop_back()
-        }
+        self.list.pop_back()
     }
 }
 
@@ -347,9 +1055,9 @@ impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
     }
 }
 
-impl<T> ExactSizeIterator for LinkedList<T> {
+impl<T, A: NodeAllocator<T>> ExactSizeIterator for IntoIter<T, A> {
     fn len(&self) -> usize {
-        self.len
+        self.list.len
     }
 }
 
@@ -365,19 +1073,15 @@ impl<'a, T> ExactSizeIterator for IterMut<'a, T> {
     }
 }
 
-impl<T> Default for LinkedList<T> {
+impl<T, A: NodeAllocator<T> + Default> Default for LinkedList<T, A> {
     fn default() -> Self {
-        Self {
-            front: None,
-            back: None,
-            len: 0,
-        }
+        Self::new_in(A::default())
     }
 }
 
-unsafe impl<T: Send> Send for LinkedList<T> {}
+unsafe impl<T: Send, A: NodeAllocator<T> + Send> Send for LinkedList<T, A> {}
 
-unsafe impl<T: Sync> Sync for LinkedList<T> {}
+unsafe impl<T: Sync, A: NodeAllocator<T> + Sync> Sync for LinkedList<T, A> {}
 
 unsafe impl<'a, T: Send> Send for Iter<'a, T> {}
 
@@ -387,12 +1091,107 @@ unsafe impl<'a, T: Send> Send for IterMut<'a, T> {}
 
 unsafe impl<'a, T: Sync> Sync for IterMut<'a, T> {}
 
-pub struct CursorMut<'a, T> {
+pub struct CursorMut<'a, T, A: NodeAllocator<T> = Global> {
+    cur: Link<T>,
+    list: &'a mut LinkedList<T, A>,
+    index: Option<usize>,
+}
+
+pub struct Cursor<'a, T, A: NodeAllocator<T> = Global> {
     cur: Link<T>,
-    list: &'a mut LinkedList<T>,
+    list: &'a LinkedList<T, A>,
     index: Option<usize>,
 }
 
+impl<'a, T, A: NodeAllocator<T>> Clone for Cursor<'a, T, A> {
+    fn clone(&self) -> Self {
+        Cursor {
+            cur: self.cur,
+            list: self.list,
+            index: self.index,
+        }
+    }
+}
+
+unsafe impl<'a, T: Sync, A: NodeAllocator<T> + Sync> Send for Cursor<'a, T, A> {}
+
+unsafe impl<'a, T: Sync, A: NodeAllocator<T> + Sync> Sync for Cursor<'a, T, A> {}
+
+impl<'a, T, A: NodeAllocator<T>> Cursor<'a, T, A> {
+    pub fn move_next(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                self.cur = (*cur.as_ptr()).back;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() += 1;
+                } else {
+                    // We just walked to the ghost, no more index
+                    self.index = None;
+                }
+            }
+        } else if let Some(front) = self.list.front {
+            // We're at the ghost, and there is a real front, so move to it!
+            self.cur = Some(front);
+            self.index = Some(0)
+        } else {
+            // We're at the ghost, but that's the only element... do nothing.
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                // We're on a real element, go to its previous (front)
+                self.cur = (*cur.as_ptr()).front;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() -= 1;
+                } else {
+                    // We just walked to the ghost, no more index
+                    self.index = None;
+                }
+            }
+        } else if let Some(back) = self.list.back {
+            // We're at the ghost, and there is a real back, so move to it!
+            self.cur = Some(back);
+            self.index = Some(self.list.len - 1)
+        } else {
+            // We're at the ghost, but that's the only element... do nothing.
+        }
+    }
+
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn current(&self) -> Option<&'a T> {
+        unsafe { self.cur.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        unsafe {
+            let next = if let Some(cur) = self.cur {
+                (*cur.as_ptr()).back
+            } else {
+                // Ghost case, try to use the list's front pointer
+                self.list.front
+            };
+            next.map(|node| &(*node.as_ptr()).elem)
+        }
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        unsafe {
+            let prev = if let Some(cur) = self.cur {
+                (*cur.as_ptr()).front
+            } else {
+                // Ghost case, try to use the list's back pointer
+                self.list.back
+            };
+            prev.map(|node| &(*node.as_ptr()).elem)
+        }
+    }
+}
+
 trait OptionExtension<T> {
     fn if_some<F>(&mut self, f: F)
     where
@@ -411,7 +1210,7 @@ impl<T> OptionExtension<T> for Option<T> {
     }
 }
 
-impl<'a, T> CursorMut<'a, T> {
+impl<'a, T, A: NodeAllocator<T>> CursorMut<'a, T, A> {
     pub fn move_next(&mut self) {
         if let Some(cur) = self.cur {
             unsafe {
@@ -460,6 +1259,40 @@ impl<'a, T> CursorMut<'a, T> {
         self.index
     }
 
+    pub fn seek(&mut self, at: usize) {
+        if at >= self.list.len {
+            // Out of bounds, park on the ghost
+            self.cur = None;
+            self.index = None;
+            return;
+        }
+
+        let from_end = at.min(self.list.len - 1 - at);
+        match self.index {
+            // Walking from where we already are is cheaper than either end
+            Some(idx) if idx.abs_diff(at) < from_end => {
+                for _ in idx..at {
+                    self.move_next();
+                }
+                for _ in at..idx {
+                    self.move_prev();
+                }
+            }
+            _ => {
+                self.cur = self.list.node_at(at);
+                self.index = Some(at);
+            }
+        }
+    }
+
+    pub fn as_cursor(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            cur: self.cur,
+            list: self.list,
+            index: self.index,
+        }
+    }
+
     pub fn current(&mut self) -> Option<&mut T> {
         unsafe { self.cur.map(|node| &mut (*node.as_ptr()).elem) }
     }
@@ -494,7 +1327,7 @@ impl<'a, T> CursorMut<'a, T> {
         }
     }
 
-    pub fn split_before(&mut self) -> LinkedList<T> {
+    pub fn split_before(&mut self) -> LinkedList<T, A> {
         // We have this:
         //
         //     list.front -> A <-> B <-> C <-> D <- list.back
@@ -525,9 +1358,9 @@ impl<'a, T> CursorMut<'a, T> {
                 let new_back = self.list.back;
                 let new_idx = Some(0);
 
-                // What the output will become
+                // What the output will become, nothing if cur was the front
                 let output_len = old_len - new_len;
-                let output_front = self.list.front;
+                let output_front = prev.and(self.list.front);
                 let output_back = prev;
 
                 // Break the links between cur and prev
@@ -541,21 +1374,25 @@ impl<'a, T> CursorMut<'a, T> {
                 self.list.front = new_front;
                 self.list.back = new_back;
                 self.index = new_idx;
+                self.list.debug_validate();
 
-                LinkedList {
+                let output = LinkedList {
                     front: output_front,
                     back: output_back,
                     len: output_len,
-                }
+                    alloc: self.list.alloc.clone(),
+                };
+                output.debug_validate();
+                output
             }
         } else {
             // We're at the ghost, just replace our list with an empty one.
             // No other state needs to be changed.
-            std::mem::replace(self.list, LinkedList::new())
+            self.list.take_all()
         }
     }
 
-    pub fn split_after(&mut self) -> LinkedList<T> {
+    pub fn split_after(&mut self) -> LinkedList<T, A> {
         // We have this:
         //
         //     list.front -> A <-> B <-> C <-> D <- list.back
@@ -586,10 +1423,10 @@ impl<'a, T> CursorMut<'a, T> {
                 let new_front = self.list.front;
                 let new_idx = Some(old_idx);
 
-                // What the output will become
+                // What the output will become, nothing if cur was the back
                 let output_len = old_len - new_len;
                 let output_front = next;
-                let output_back = self.list.back;
+                let output_back = next.and(self.list.back);
 
                 // Break the links between cur and next
                 if let Some(next) = next {
@@ -602,21 +1439,25 @@ impl<'a, T> CursorMut<'a, T> {
                 self.list.front = new_front;
                 self.list.back = new_back;
                 self.index = new_idx;
+                self.list.debug_validate();
 
-                LinkedList {
+                let output = LinkedList {
                     front: output_front,
                     back: output_back,
                     len: output_len,
-                }
+                    alloc: self.list.alloc.clone(),
+                };
+                output.debug_validate();
+                output
             }
         } else {
             // We're at the ghost, just replace our list with an empty one.
             // No other state needs to be changed.
-            std::mem::replace(self.list, LinkedList::new())
+            self.list.take_all()
         }
     }
 
-    pub fn splice_before(&mut self, mut input: LinkedList<T>) {
+    pub fn splice_before(&mut self, input: LinkedList<T, A>) {
         // We have this:
         //
         // input.front -> 1 <-> 2 <- input.back
@@ -632,6 +1473,7 @@ impl<'a, T> CursorMut<'a, T> {
         //                                 ^
         //                                cur
         //
+        let mut input = self.list.adopt(input);
         unsafe {
             // We can either `take` the input's pointers or `mem::forget`
             // it. Using `take` is more responsible in case we ever do custom
@@ -676,9 +1518,10 @@ impl<'a, T> CursorMut<'a, T> {
 
             // Input dropped here
         }
+        self.list.debug_validate();
     }
 
-    pub fn splice_after(&mut self, mut input: LinkedList<T>) {
+    pub fn splice_after(&mut self, input: LinkedList<T, A>) {
         // We have this:
         //
         // input.front -> 1 <-> 2 <- input.back
@@ -687,56 +1530,186 @@ impl<'a, T> CursorMut<'a, T> {
         //                     ^
         //                    cur
         //
-        //
+        //
+        // Becoming this:
+        //
+        // list.front -> A <-> B <-> 1 <-> 2 <-> C <- list.back
+        //                     ^
+        //                    cur
+        //
+        let mut input = self.list.adopt(input);
+        unsafe {
+            // We can either `take` the input's pointers or `mem::forget`
+            // it. Using `take` is more responsible in case we ever do custom
+            // allocators or something that also needs to be cleaned up!
+            if input.is_empty() {
+                // Input is empty, do nothing.
+            } else if let Some(cur) = self.cur {
+                // Both lists are non-empty
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                if let Some(next) = (*cur.as_ptr()).back {
+                    // General Case, no boundaries, just internal fixups
+                    (*next.as_ptr()).front = Some(in_back);
+                    (*in_back.as_ptr()).back = Some(next);
+                    (*cur.as_ptr()).back = Some(in_front);
+                    (*in_front.as_ptr()).front = Some(cur);
+                } else {
+                    // No next, we're appending to the back
+                    (*cur.as_ptr()).back = Some(in_front);
+                    (*in_front.as_ptr()).front = Some(cur);
+                    self.list.back = Some(in_back);
+                }
+                // Index doesn't change
+            } else if let Some(front) = self.list.front {
+                // We're on the ghost but non-empty, append to the front
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                (*front.as_ptr()).front = Some(in_back);
+                (*in_back.as_ptr()).back = Some(front);
+                self.list.front = Some(in_front);
+            } else {
+                // We're empty, become the input, remain on the ghost
+                std::mem::swap(self.list, &mut input);
+            }
+
+            self.list.len += input.len;
+            // Not necessary but Polite To Do
+            input.len = 0;
+
+            // Input dropped here
+        }
+        self.list.debug_validate();
+    }
+
+    pub fn insert_before(&mut self, elem: T) {
+        // list.front -> A <-> B <-> C <- list.back
+        //                     ^
+        //                    cur
+        //
+        // Becoming this:
+        //
+        // list.front -> A <-> x <-> B <-> C <- list.back
+        //                           ^
+        //                          cur
+        //
+        // On the ghost "before" means at the back of the list.
+        unsafe {
+            let new = self.list.alloc_node(elem);
+
+            if let Some(cur) = self.cur {
+                if let Some(prev) = (*cur.as_ptr()).front {
+                    (*prev.as_ptr()).back = Some(new);
+                    (*new.as_ptr()).front = Some(prev);
+                } else {
+                    self.list.front = Some(new);
+                }
+                (*new.as_ptr()).back = Some(cur);
+                (*cur.as_ptr()).front = Some(new);
+                // Index moves forward by one
+                *self.index.as_mut().unwrap() += 1;
+            } else if let Some(back) = self.list.back {
+                (*back.as_ptr()).back = Some(new);
+                (*new.as_ptr()).front = Some(back);
+                self.list.back = Some(new);
+            } else {
+                // Empty list, the new node is the whole list
+                self.list.front = Some(new);
+                self.list.back = Some(new);
+            }
+            self.list.len += 1;
+        }
+        self.list.debug_validate();
+    }
+
+    pub fn insert_after(&mut self, elem: T) {
+        // list.front -> A <-> B <-> C <- list.back
+        //                     ^
+        //                    cur
+        //
         // Becoming this:
         //
-        // list.front -> A <-> B <-> 1 <-> 2 <-> C <- list.back
+        // list.front -> A <-> B <-> x <-> C <- list.back
         //                     ^
         //                    cur
         //
+        // On the ghost "after" means at the front of the list.
         unsafe {
-            // We can either `take` the input's pointers or `mem::forget`
-            // it. Using `take` is more responsible in case we ever do custom
-            // allocators or something that also needs to be cleaned up!
-            if input.is_empty() {
-                // Input is empty, do nothing.
-            } else if let Some(cur) = self.cur {
-                // Both lists are non-empty
-                let in_front = input.front.take().unwrap();
-                let in_back = input.back.take().unwrap();
+            let new = self.list.alloc_node(elem);
 
+            if let Some(cur) = self.cur {
                 if let Some(next) = (*cur.as_ptr()).back {
-                    // General Case, no boundaries, just internal fixups
-                    (*next.as_ptr()).front = Some(in_back);
-                    (*in_back.as_ptr()).back = Some(next);
-                    (*cur.as_ptr()).back = Some(in_front);
-                    (*in_front.as_ptr()).front = Some(cur);
+                    (*next.as_ptr()).front = Some(new);
+                    (*new.as_ptr()).back = Some(next);
                 } else {
-                    // No next, we're appending to the back
-                    (*cur.as_ptr()).back = Some(in_front);
-                    (*in_front.as_ptr()).front = Some(cur);
-                    self.list.back = Some(in_back);
+                    self.list.back = Some(new);
                 }
+                (*new.as_ptr()).front = Some(cur);
+                (*cur.as_ptr()).back = Some(new);
                 // Index doesn't change
             } else if let Some(front) = self.list.front {
-                // We're on the ghost but non-empty, append to the front
-                let in_front = input.front.take().unwrap();
-                let in_back = input.back.take().unwrap();
-
-                (*front.as_ptr()).front = Some(in_back);
-                (*in_back.as_ptr()).back = Some(front);
-                self.list.front = Some(in_front);
+                (*front.as_ptr()).front = Some(new);
+                (*new.as_ptr()).back = Some(front);
+                self.list.front = Some(new);
             } else {
-                // We're empty, become the input, remain on the ghost
-                std::mem::swap(self.list, &mut input);
+                // Empty list, the new node is the whole list
+                self.list.front = Some(new);
+                self.list.back = Some(new);
             }
+            self.list.len += 1;
+        }
+        self.list.debug_validate();
+    }
 
-            self.list.len += input.len;
-            // Not necessary but Polite To Do
-            input.len = 0;
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.unlink_current()
+            .map(|node| unsafe { self.list.free_node(node).elem })
+    }
 
-            // Input dropped here
+    pub fn remove_current_as_list(&mut self) -> Option<LinkedList<T, A>> {
+        self.unlink_current().map(|node| LinkedList {
+            front: Some(node),
+            back: Some(node),
+            len: 1,
+            alloc: self.list.alloc.clone(),
+        })
+    }
+
+    pub fn replace_current(&mut self, elem: T) -> Result<T, T> {
+        // On the ghost there is nothing to replace, hand the element back
+        match self.current() {
+            Some(cur) => Ok(std::mem::replace(cur, elem)),
+            None => Err(elem),
+        }
+    }
+
+    fn unlink_current(&mut self) -> Option<NonNull<Node<T>>> {
+        // We have this:
+        //
+        //     list.front -> A <-> B <-> C <- list.back
+        //                         ^
+        //                        cur
+        //
+        // And we want to produce this, handing B back detached:
+        //
+        //     list.front -> A <-> C <- list.back
+        //                         ^
+        //                        cur
+        //
+        // The index stays the same since C takes B's place. If there was
+        // no C we walk onto the ghost instead.
+        let cur = self.cur?;
+        unsafe {
+            let next = (*cur.as_ptr()).back;
+            self.list.unlink_node(cur);
+            if next.is_none() {
+                self.index = None;
+            }
+            self.cur = next;
         }
+        Some(cur)
     }
 }
 
@@ -754,6 +1727,12 @@ fn assert_properties() {
     is_send::<IterMut<i32>>();
     is_sync::<IterMut<i32>>();
 
+    is_send::<Cursor<i32>>();
+    is_sync::<Cursor<i32>>();
+
+    is_send::<IntoIter<i32>>();
+    is_sync::<IntoIter<i32>>();
+
     fn linked_list_covariant<'a, T>(x: LinkedList<&'static T>) -> LinkedList<&'a T> {
         x
     }
@@ -762,9 +1741,20 @@ fn assert_properties() {
     }
 }
 
+/// The list itself is not an iterator, so adapters can't drain it by accident:
+///
+/// ```compile_fail
+/// use lists::a_production_linked_list::LinkedList;
+///
+/// let mut list: LinkedList<i32> = (0..3).collect();
+/// list.next();
+/// ```
+#[allow(dead_code)]
+fn assert_not_an_iterator() {}
+
 #[cfg(test)]
 mod test {
-    use super::LinkedList;
+    use super::{LinkedList, NodeAllocator, NodePool};
 
     fn generate_test() -> LinkedList<i32> {
         list_from(&[0, 1, 2, 3, 4, 5, 6])
@@ -1075,6 +2065,488 @@ mod test {
         assert_eq!(cursor.index(), Some(4));
     }
 
+    #[test]
+    fn test_cursor_front_back() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        assert_eq!(m.cursor_front().current(), None);
+        assert_eq!(m.cursor_back().index(), None);
+        assert_eq!(m.cursor_front_mut().current(), None);
+
+        m.extend([1, 2, 3]);
+        let mut front = m.cursor_front();
+        let mut back = m.cursor_back();
+        assert_eq!(front.current(), Some(&1));
+        assert_eq!(front.index(), Some(0));
+        assert_eq!(back.current(), Some(&3));
+        assert_eq!(back.index(), Some(2));
+
+        // Several read-only cursors can be live at once
+        let first = front.current();
+        front.move_next();
+        back.move_prev();
+        assert_eq!(front.current(), back.current());
+        assert_eq!(first, Some(&1));
+        assert_eq!(front.peek_prev(), Some(&1));
+        assert_eq!(front.peek_next(), Some(&3));
+
+        let mut copy = front.clone();
+        copy.move_next();
+        copy.move_next();
+        assert_eq!(copy.current(), None);
+        assert_eq!(copy.index(), None);
+        assert_eq!(copy.peek_next(), Some(&1));
+        assert_eq!(copy.peek_prev(), Some(&3));
+        assert_eq!(front.index(), Some(1));
+
+        let mut cursor = m.cursor_back_mut();
+        assert_eq!(cursor.current(), Some(&mut 3));
+        cursor.move_prev();
+        {
+            let view = cursor.as_cursor();
+            assert_eq!(view.current(), Some(&2));
+            assert_eq!(view.index(), Some(1));
+            assert_eq!(view.peek_next(), Some(&3));
+        }
+        cursor.insert_after(4);
+
+        let mut cursor = m.cursor_front_mut();
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 2, 4, 3]);
+    }
+
+    #[test]
+    fn test_positional() {
+        let mut m = generate_test();
+        for i in 0..7 {
+            assert_eq!(m.get(i), Some(&(i as i32)));
+        }
+        assert_eq!(m.get(7), None);
+        *m.get_mut(5).unwrap() = 50;
+        *m.get_mut(1).unwrap() = 10;
+        assert_eq!(m.get_mut(7), None);
+
+        m.insert(0, -1);
+        m.insert(8, 7);
+        m.insert(3, 100);
+        m.insert(7, 200);
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[-1, 0, 10, 100, 2, 3, 4, 200, 50, 6, 7]
+        );
+
+        assert_eq!(m.remove(11), None);
+        assert_eq!(m.remove(3), Some(100));
+        assert_eq!(m.remove(6), Some(200));
+        assert_eq!(m.remove(0), Some(-1));
+        assert_eq!(m.remove(7), Some(7));
+        check_links(&m);
+        assert_eq!(m.len(), 7);
+        assert_eq!(m.back(), Some(&6));
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[0, 10, 2, 3, 4, 50, 6]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_out_of_bounds() {
+        let mut m = generate_test();
+        m.insert(8, 0);
+    }
+
+    #[test]
+    fn test_append_prepend() {
+        let mut m = list_from(&[1, 2, 3]);
+        let mut n = list_from(&[4, 5]);
+        m.append(&mut n);
+        assert!(n.is_empty());
+        check_links(&m);
+        check_links(&n);
+        assert_eq!(m.len(), 5);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 2, 3, 4, 5]);
+
+        let mut n = list_from(&[-1, 0]);
+        m.prepend(&mut n);
+        assert!(n.is_empty());
+        check_links(&m);
+        assert_eq!(m.front(), Some(&-1));
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[-1, 0, 1, 2, 3, 4, 5]
+        );
+
+        // Empty on either side
+        m.append(&mut n);
+        m.prepend(&mut n);
+        assert_eq!(m.len(), 7);
+        let mut e = LinkedList::new();
+        e.append(&mut m);
+        assert!(m.is_empty());
+        assert_eq!(e.len(), 7);
+        let mut e2 = LinkedList::new();
+        e2.prepend(&mut e);
+        check_links(&e2);
+        assert_eq!(e2.back(), Some(&5));
+        assert_eq!(e2.len(), 7);
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut m = generate_test();
+        let tail = m.split_off(5);
+        check_links(&m);
+        check_links(&tail);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[0, 1, 2, 3, 4]);
+        assert_eq!(tail.iter().cloned().collect::<Vec<_>>(), &[5, 6]);
+        assert_eq!(tail.len(), 2);
+
+        let tail = m.split_off(1);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[0]);
+        assert_eq!(tail.iter().cloned().collect::<Vec<_>>(), &[1, 2, 3, 4]);
+        assert_eq!(m.back(), Some(&0));
+
+        let tail = m.split_off(1);
+        assert!(tail.is_empty());
+        assert_eq!(m.len(), 1);
+
+        let tail = m.split_off(0);
+        assert!(m.is_empty());
+        assert_eq!(tail.iter().cloned().collect::<Vec<_>>(), &[0]);
+    }
+
+    #[test]
+    fn test_split_off_at_ends() {
+        let mut m = generate_test();
+        let mut tail = m.split_off(m.len());
+        assert!(tail.is_empty());
+        assert_eq!(tail.front(), None);
+        assert_eq!(tail.back(), None);
+        tail.push_back(99);
+        check_links(&m);
+        check_links(&tail);
+        assert_eq!(m.back(), Some(&6));
+        assert_eq!(tail.iter().cloned().collect::<Vec<_>>(), &[99]);
+
+        let mut m = generate_test();
+        let mut tail = m.split_off(0);
+        assert!(m.is_empty());
+        assert_eq!(m.back(), None);
+        m.push_back(99);
+        check_links(&m);
+        check_links(&tail);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[99]);
+        tail.push_front(-1);
+        check_links(&tail);
+        assert_eq!(tail.len(), 8);
+    }
+
+    #[test]
+    fn test_cursor_split_at_ends() {
+        let mut m = generate_test();
+        let mut cursor = m.cursor_front_mut();
+        let front = cursor.split_before();
+        assert!(front.is_empty());
+        assert_eq!(front.front(), None);
+        assert_eq!(front.back(), None);
+
+        let mut cursor = m.cursor_back_mut();
+        let back = cursor.split_after();
+        assert!(back.is_empty());
+        assert_eq!(back.front(), None);
+        assert_eq!(back.back(), None);
+        drop((front, back));
+
+        check_links(&m);
+        assert_eq!(m.len(), 7);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_off_out_of_bounds() {
+        let mut m = generate_test();
+        m.split_off(8);
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut m: LinkedList<i32> = (0..10).collect();
+        m.retain(|x| x % 3 != 0);
+        check_links(&m);
+        assert_eq!(m.len(), 6);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 2, 4, 5, 7, 8]);
+
+        m.retain_mut(|x| {
+            *x *= 10;
+            *x > 20
+        });
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[40, 50, 70, 80]);
+
+        m.retain(|_| false);
+        assert!(m.is_empty());
+        assert_eq!(m.front(), None);
+        assert_eq!(m.back(), None);
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut m: LinkedList<i32> = (0..10).collect();
+        let mut extracted = m.extract_if(|x| *x % 2 == 0);
+        assert_eq!(extracted.size_hint(), (0, Some(10)));
+        assert_eq!(extracted.next(), Some(0));
+        assert_eq!(extracted.next(), Some(2));
+        assert_eq!(extracted.size_hint(), (0, Some(7)));
+        // Dropping early keeps whatever was not visited yet
+        drop(extracted);
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[1, 3, 4, 5, 6, 7, 8, 9]
+        );
+
+        let evens: Vec<_> = m.extract_if(|x| *x % 2 == 0).collect();
+        assert_eq!(evens, &[4, 6, 8]);
+        check_links(&m);
+        assert_eq!(m.len(), 5);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 3, 5, 7, 9]);
+
+        let all: Vec<_> = m.extract_if(|_| true).collect();
+        assert_eq!(all, &[1, 3, 5, 7, 9]);
+        assert!(m.is_empty());
+        assert_eq!(m.extract_if(|_| true).next(), None);
+    }
+
+    #[test]
+    fn test_extract_if_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut m: LinkedList<i32> = (0..6).collect();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            m.extract_if(|x| {
+                assert_ne!(*x, 3);
+                *x % 2 == 0
+            })
+            .for_each(drop);
+        }));
+        assert!(result.is_err());
+        check_links(&m);
+        assert_eq!(m.len(), 4);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut m: LinkedList<i32> = LinkedList::new();
+        m.sort();
+        assert!(m.is_empty());
+
+        for len in 1..40 {
+            // A cheap deterministic shuffle with plenty of duplicates
+            let v: Vec<i32> = (0..len).map(|i| (i * 7919 + 13) % 17).collect();
+            let mut m = list_from(&v);
+            m.sort();
+            check_links(&m);
+            let mut expected = v.clone();
+            expected.sort();
+            assert_eq!(m.len(), len as usize);
+            assert_eq!(m.iter().cloned().collect::<Vec<_>>(), expected);
+            assert_eq!(m.front(), expected.first());
+            assert_eq!(m.back(), expected.last());
+        }
+
+        let mut m = generate_test();
+        m.sort_by(|a, b| b.cmp(a));
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[6, 5, 4, 3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_sort_stable() {
+        // Not Clone, so nothing can be copied around behind our back
+        #[derive(Debug, PartialEq, Eq)]
+        struct Record {
+            key: u8,
+            seq: usize,
+        }
+
+        let mut m: LinkedList<Record> = (0..100)
+            .map(|seq| Record {
+                key: (seq * 31 % 5) as u8,
+                seq,
+            })
+            .collect();
+        m.sort_by_key(|r| r.key);
+        check_links(&m);
+        let sorted: Vec<_> = m.iter().map(|r| (r.key, r.seq)).collect();
+        let mut expected = sorted.clone();
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn test_sort_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let v: Vec<i32> = (0..50).map(|i| (i * 37) % 50).collect();
+        for limit in [0, 1, 10, 60, 150] {
+            let mut m = list_from(&v);
+            let mut calls = 0;
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                m.sort_by(|a, b| {
+                    calls += 1;
+                    assert!(calls <= limit);
+                    a.cmp(b)
+                })
+            }));
+            assert!(result.is_err());
+            // Every element is still there and the links agree
+            check_links(&m);
+            assert_eq!(m.len(), v.len());
+            assert_eq!(m.iter().count(), v.len());
+            assert_eq!(m.iter().rev().count(), v.len());
+            let mut seen = m.iter().cloned().collect::<Vec<_>>();
+            seen.sort();
+            assert_eq!(seen, (0..50).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_miri_food() {
+        // Interleave every way of getting at the elements mutably. Miri
+        // checks none of them alias; this module and an_ok_unsafe_queue
+        // both run clean under its default Stacked Borrows model with
+        //
+        //     cargo +nightly miri test --lib a_production_linked_list
+        //     cargo +nightly miri test --lib an_ok_unsafe_queue
+        let mut m: LinkedList<i32> = (0..6).collect();
+        if let Some(x) = m.peek_mut() {
+            *x += 100;
+        }
+        for x in m.iter_mut() {
+            *x *= 2;
+        }
+        let mut it = m.iter_mut();
+        let a = it.next().unwrap();
+        let b = it.next_back().unwrap();
+        std::mem::swap(a, b);
+        let rest: Vec<_> = it.collect();
+        assert_eq!(rest, [&mut 2, &mut 4, &mut 6, &mut 8]);
+        assert_eq!(m.peek(), Some(&10));
+        assert_eq!(m.back(), Some(&200));
+
+        *m.front_mut().unwrap() += 1;
+        *m.back_mut().unwrap() += 1;
+        *m.get_mut(3).unwrap() += 1;
+        let mut cursor = m.cursor_front_mut();
+        cursor.move_next();
+        *cursor.current().unwrap() += 1;
+        *cursor.peek_next().unwrap() += 1;
+        *cursor.peek_prev().unwrap() += 1;
+        assert_eq!(cursor.as_cursor().current(), Some(&3));
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[12, 3, 5, 7, 8, 201]
+        );
+    }
+
+    #[test]
+    fn test_into_iterator() {
+        let mut m = generate_test();
+        let mut sum = 0;
+        for x in &m {
+            sum += *x;
+        }
+        assert_eq!(sum, 21);
+        for x in &mut m {
+            *x *= 2;
+        }
+        // Borrowing iteration leaves the list alone
+        assert_eq!(m.len(), 7);
+
+        let mut it = m.into_iter();
+        assert_eq!(it.size_hint(), (7, Some(7)));
+        assert_eq!(it.next(), Some(0));
+        assert_eq!(it.next_back(), Some(12));
+        assert_eq!(it.len(), 5);
+        assert_eq!(it.rev().collect::<Vec<_>>(), &[10, 8, 6, 4, 2]);
+    }
+
+    #[test]
+    fn test_validate() {
+        use super::InvariantError;
+        use std::ptr::NonNull;
+
+        let mut m = generate_test();
+        assert_eq!(m.validate(), Ok(()));
+        assert_eq!(LinkedList::<i32>::new().validate(), Ok(()));
+
+        m.len = 8;
+        assert_eq!(
+            m.validate(),
+            Err(InvariantError::LenMismatch { len: 8, counted: 7 })
+        );
+        m.len = 7;
+
+        let back = m.back.take();
+        assert_eq!(m.validate(), Err(InvariantError::EndsDisagree));
+        m.back = m.front;
+        assert_eq!(m.validate(), Err(InvariantError::BackNotLast));
+        m.back = back;
+
+        unsafe {
+            let second: NonNull<_> = (*m.front.unwrap().as_ptr()).back.unwrap();
+            let third = (*second.as_ptr()).back.unwrap();
+            (*third.as_ptr()).front = None;
+            assert_eq!(
+                m.validate(),
+                Err(InvariantError::BrokenBackLink { index: 2 })
+            );
+            (*third.as_ptr()).front = Some(second);
+
+            // Tie the last node back to the third one
+            let last = m.back.unwrap();
+            (*last.as_ptr()).back = Some(third);
+            assert_eq!(m.validate(), Err(InvariantError::Cycle));
+            (*last.as_ptr()).back = None;
+        }
+        assert_eq!(m.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_cursor_seek() {
+        let mut m: LinkedList<u32> = (0..10).collect();
+        let mut cursor = m.cursor_mut();
+        cursor.seek(3);
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.index(), Some(3));
+        cursor.seek(4);
+        assert_eq!(cursor.current(), Some(&mut 4));
+        cursor.seek(2);
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.index(), Some(2));
+        cursor.seek(8);
+        assert_eq!(cursor.current(), Some(&mut 8));
+        assert_eq!(cursor.index(), Some(8));
+        cursor.seek(0);
+        assert_eq!(cursor.current(), Some(&mut 0));
+        cursor.seek(9);
+        assert_eq!(cursor.current(), Some(&mut 9));
+        cursor.seek(10);
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.index(), None);
+        cursor.seek(5);
+        assert_eq!(cursor.current(), Some(&mut 5));
+        assert_eq!(cursor.peek_prev(), Some(&mut 4));
+    }
+
     #[test]
     fn test_cursor_mut_insert() {
         let mut m: LinkedList<u32> = LinkedList::new();
@@ -1099,7 +2571,6 @@ mod test {
             &[10, 7, 1, 8, 2, 3, 4, 5, 6, 9]
         );
 
-        /* remove_current not impl'd
         let mut cursor = m.cursor_mut();
         cursor.move_next();
         cursor.move_prev();
@@ -1115,7 +2586,6 @@ mod test {
         assert_eq!(cursor.remove_current(), Some(10));
         check_links(&m);
         assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 8, 2, 3, 4, 5, 6]);
-        */
 
         let mut m: LinkedList<u32> = LinkedList::new();
         m.extend([1, 8, 2, 3, 4, 5, 6]);
@@ -1158,7 +2628,155 @@ mod test {
         );
     }
 
-    fn check_links<T: Eq + std::fmt::Debug>(list: &LinkedList<T>) {
+    #[test]
+    fn test_cursor_mut_insert_single() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let mut cursor = m.cursor_mut();
+        cursor.insert_before(2);
+        cursor.insert_after(1);
+        cursor.insert_before(3);
+        assert_eq!(cursor.index(), None);
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 2, 3]);
+
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.insert_before(10);
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.index(), Some(1));
+        cursor.insert_after(11);
+        assert_eq!(cursor.peek_next(), Some(&mut 11));
+        assert_eq!(cursor.index(), Some(1));
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.index(), Some(4));
+        cursor.insert_after(12);
+        cursor.insert_before(13);
+        assert_eq!(cursor.index(), Some(5));
+        check_links(&m);
+        assert_eq!(m.len(), 7);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[10, 1, 11, 2, 13, 3, 12]
+        );
+        assert_eq!(m.back(), Some(&12));
+    }
+
+    #[test]
+    fn test_cursor_mut_remove_replace() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        m.extend([1, 2, 3, 4]);
+        let mut cursor = m.cursor_mut();
+        assert_eq!(cursor.replace_current(9), Err(9));
+        assert!(cursor.remove_current_as_list().is_none());
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.replace_current(20), Ok(2));
+        assert_eq!(cursor.current(), Some(&mut 20));
+
+        let removed = cursor.remove_current_as_list().unwrap();
+        assert_eq!(removed.len(), 1);
+        check_links(&removed);
+        assert_eq!(removed.iter().cloned().collect::<Vec<_>>(), &[20]);
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.index(), Some(1));
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_prev(), Some(&mut 3));
+        check_links(&m);
+        assert_eq!(m.len(), 2);
+        assert_eq!(m.back(), Some(&3));
+
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.remove_current(), Some(3));
+        assert_eq!(cursor.remove_current(), None);
+        assert!(m.is_empty());
+        assert_eq!(m.front(), None);
+        assert_eq!(m.back(), None);
+    }
+
+    #[test]
+    fn test_node_pool() {
+        let mut list = LinkedList::with_node_pool(4);
+        assert_eq!(list.allocator().capacity(), 4);
+
+        // Churning push/pop pairs keeps reusing the same slots
+        for i in 0..1000 {
+            list.push_back(i);
+            list.push_front(i);
+            assert_eq!(list.pop_back(), Some(i));
+            assert_eq!(list.pop_front(), Some(i));
+        }
+        assert_eq!(list.allocator().capacity(), 4);
+        assert_eq!(list.allocator().available(), 4);
+
+        // Running out grows the pool instead of failing
+        list.extend(0..10);
+        check_links(&list);
+        assert_eq!(list.allocator().capacity(), 12);
+        assert_eq!(list.allocator().available(), 2);
+
+        // Everything the cursor frees goes back too
+        list.retain(|x| x % 2 == 0);
+        list.cursor_front_mut().remove_current();
+        assert_eq!(list.allocator().available(), 8);
+        assert_eq!(list.iter().cloned().collect::<Vec<_>>(), &[2, 4, 6, 8]);
+
+        drop(list);
+    }
+
+    #[test]
+    fn test_node_pool_shared() {
+        let mut m = LinkedList::with_node_pool(8);
+        m.extend(0..6);
+
+        // Lists split off a pooled list share its pool
+        let mut n = m.split_off(3);
+        assert!(m.allocator().shares_nodes_with(n.allocator()));
+        assert_eq!(n.allocator().available(), 2);
+        n.push_back(6);
+        assert_eq!(m.allocator().available(), 1);
+
+        // The pool outlives whichever list is dropped first
+        let pool = m.allocator().clone();
+        drop(m);
+        assert_eq!(pool.available(), 4);
+        assert_eq!(n.iter().cloned().collect::<Vec<_>>(), &[3, 4, 5, 6]);
+
+        // Splicing in nodes from another pool moves the elements over
+        let mut other = LinkedList::new_in(NodePool::with_capacity(2));
+        other.extend([7, 8]);
+        assert!(!other.allocator().shares_nodes_with(n.allocator()));
+        n.append(&mut other);
+        check_links(&n);
+        assert_eq!(other.allocator().available(), 2);
+        assert_eq!(pool.available(), 2);
+        assert_eq!(n.iter().cloned().collect::<Vec<_>>(), &[3, 4, 5, 6, 7, 8]);
+
+        let mut other = LinkedList::new_in(other.allocator().clone());
+        other.push_back(2);
+        n.prepend(&mut other);
+        n.cursor_mut()
+            .splice_after(LinkedList::new_in(NodePool::default()));
+        check_links(&n);
+        assert_eq!(n.front(), Some(&2));
+        assert_eq!(pool.available(), 1);
+    }
+
+    fn check_links<T, A>(list: &LinkedList<T, A>)
+    where
+        T: Eq + std::fmt::Debug,
+        A: NodeAllocator<T>,
+    {
+        list.validate().unwrap();
+
         let from_front: Vec<_> = list.iter().collect();
         let from_back: Vec<_> = list.iter().rev().collect();
         let re_reved: Vec<_> = from_back.into_iter().rev().collect();
@@ -1,6 +1,8 @@
 use std::ops::{Deref, DerefMut};
 use std::ptr;
 
+use crate::invariant::{has_cycle, InvariantError};
+
 type NodeRef<T> = *mut Node<T>;
 
 pub struct List<T> {
@@ -98,10 +100,11 @@ impl<T> List<T> {
             }
             self.tail = raw;
         }
+        self.debug_validate();
     }
 
     pub fn pop(&mut self) -> Option<T> {
-        unsafe {
+        let elem = unsafe {
             if self.head.is_null() {
                 None
             } else {
@@ -112,16 +115,72 @@ impl<T> List<T> {
                 }
                 Some(head.elem)
             }
-        }
+        };
+        self.debug_validate();
+        elem
     }
 
     pub fn peek(&self) -> Option<&T> {
         unsafe { self.head.as_ref().map(|node| &node.elem) }
     }
 
-    pub fn peek_mut(&self) -> Option<&mut T> {
+    /// Needs a unique borrow, so two of them can't alias the head:
+    ///
+    /// ```compile_fail
+    /// use lists::an_ok_unsafe_queue::List;
+    ///
+    /// let mut list = List::new();
+    /// list.push(1);
+    /// let shared = &list;
+    /// let a = shared.peek_mut().unwrap();
+    /// let b = shared.peek_mut().unwrap();
+    /// std::mem::swap(a, b);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use lists::an_ok_unsafe_queue::List;
+    ///
+    /// let mut list = List::new();
+    /// list.push(1);
+    /// let a = list.peek_mut().unwrap();
+    /// let b = list.peek().unwrap();
+    /// *a += *b;
+    /// ```
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
         unsafe { self.head.as_mut().map(|node| &mut node.elem) }
     }
+
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        if self.head.is_null() != self.tail.is_null() {
+            return Err(InvariantError::EndsDisagree);
+        }
+        unsafe {
+            let head = Some(self.head).filter(|head| !head.is_null());
+            let next = |node: NodeRef<T>| Some((*node).next).filter(|next| !next.is_null());
+            if has_cycle(head, next) {
+                return Err(InvariantError::Cycle);
+            }
+
+            // No cycle, so this walk terminates
+            let mut last = ptr::null_mut();
+            let mut cur = head;
+            while let Some(node) = cur {
+                last = node;
+                cur = next(node);
+            }
+            if last != self.tail {
+                return Err(InvariantError::BackNotLast);
+            }
+        }
+        Ok(())
+    }
+
+    fn debug_validate(&self) {
+        #[cfg(feature = "debug-invariants")]
+        if let Err(err) = self.validate() {
+            panic!("List invariant broken: {err}");
+        }
+    }
 }
 
 impl<T> Drop for List<T> {
@@ -213,6 +272,34 @@ mod test {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn validate() {
+        use crate::invariant::InvariantError;
+        use std::ptr;
+
+        let mut list = List::new();
+        assert_eq!(list.validate(), Ok(()));
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        assert_eq!(list.validate(), Ok(()));
+
+        let tail = list.tail;
+        list.tail = ptr::null_mut();
+        assert_eq!(list.validate(), Err(InvariantError::EndsDisagree));
+        list.tail = list.head;
+        assert_eq!(list.validate(), Err(InvariantError::BackNotLast));
+        list.tail = tail;
+
+        unsafe {
+            (*tail).next = list.head;
+            assert_eq!(list.validate(), Err(InvariantError::Cycle));
+            (*tail).next = ptr::null_mut();
+        }
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.validate(), Ok(()));
+    }
+
     #[test]
     fn miri_food() {
         let mut list = List::new();
@@ -0,0 +1,404 @@
+//! Replays random operation sequences against `a_production_linked_list`
+//! and the std collections, comparing everything after every step.
+//!
+//! A failing run is shrunk to a minimal operation sequence and reported
+//! together with its seed. Set `LISTS_DIFF_SEED` to replay a single seed.
+
+use crate::a_production_linked_list::LinkedList;
+use crate::testing::Rng;
+use std::collections::{LinkedList as StdList, VecDeque};
+use std::fmt::Debug;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+const SEEDS: u64 = 200;
+const OPS_PER_SEED: usize = 400;
+
+/// Positions are stored raw and reduced modulo the current length when the
+/// op runs, so every op stays valid while a sequence is being shrunk.
+#[derive(Clone, Debug)]
+enum Op {
+    PushFront(u32),
+    PushBack(u32),
+    PopFront,
+    PopBack,
+    MoveNext,
+    MovePrev,
+    Seek(usize),
+    InsertBefore(u32),
+    InsertAfter(u32),
+    RemoveCurrent,
+    SpliceBefore(Vec<u32>),
+    SpliceAfter(Vec<u32>),
+    SplitBefore,
+    SplitAfter,
+    SplitOff(usize),
+    Append(Vec<u32>),
+    Prepend(Vec<u32>),
+    Insert(usize, u32),
+    Remove(usize),
+    Retain(u32),
+    Sort,
+}
+
+fn gen_op(rng: &mut Rng) -> Op {
+    let value = |rng: &mut Rng| rng.below(100) as u32;
+    let values = |rng: &mut Rng| (0..rng.below(4)).map(|_| rng.below(100) as u32).collect();
+    match rng.below(21) {
+        0 => Op::PushFront(value(rng)),
+        1 => Op::PushBack(value(rng)),
+        2 => Op::PopFront,
+        3 => Op::PopBack,
+        4 => Op::MoveNext,
+        5 => Op::MovePrev,
+        6 => Op::Seek(rng.below(64) as usize),
+        7 => Op::InsertBefore(value(rng)),
+        8 => Op::InsertAfter(value(rng)),
+        9 => Op::RemoveCurrent,
+        10 => Op::SpliceBefore(values(rng)),
+        11 => Op::SpliceAfter(values(rng)),
+        12 => Op::SplitBefore,
+        13 => Op::SplitAfter,
+        14 => Op::SplitOff(rng.below(64) as usize),
+        15 => Op::Append(values(rng)),
+        16 => Op::Prepend(values(rng)),
+        17 => Op::Insert(rng.below(64) as usize, value(rng)),
+        18 => Op::Remove(rng.below(64) as usize),
+        19 => Op::Retain(rng.below(5) as u32 + 2),
+        _ => Op::Sort,
+    }
+}
+
+/// The reference side: a `VecDeque` does the positional work, the std
+/// `LinkedList` mirrors it through its own split/append API, and `cursor`
+/// is the index the `CursorMut` is expected to report.
+#[derive(Default)]
+struct Model {
+    deque: VecDeque<u32>,
+    list: StdList<u32>,
+    cursor: Option<usize>,
+}
+
+impl Model {
+    fn insert(&mut self, at: usize, elem: u32) {
+        self.deque.insert(at, elem);
+        let mut tail = self.list.split_off(at);
+        self.list.push_back(elem);
+        self.list.append(&mut tail);
+    }
+
+    fn insert_all(&mut self, at: usize, elems: &[u32]) {
+        for (i, elem) in elems.iter().enumerate() {
+            self.insert(at + i, *elem);
+        }
+    }
+
+    fn remove(&mut self, at: usize) -> Option<u32> {
+        if at >= self.deque.len() {
+            return None;
+        }
+        let mut tail = self.list.split_off(at);
+        let from_list = tail.pop_front();
+        self.list.append(&mut tail);
+        let from_deque = self.deque.remove(at);
+        assert_eq!(
+            from_list, from_deque,
+            "std LinkedList and VecDeque disagree"
+        );
+        from_deque
+    }
+
+    fn split_off(&mut self, at: usize) -> Vec<u32> {
+        let tail = self.list.split_off(at);
+        let from_deque: Vec<_> = self.deque.split_off(at).into();
+        assert!(
+            tail.iter().eq(from_deque.iter()),
+            "std LinkedList and VecDeque disagree"
+        );
+        from_deque
+    }
+}
+
+fn list_of(elems: &[u32]) -> LinkedList<u32> {
+    elems.iter().copied().collect()
+}
+
+fn check_eq<T: PartialEq + Debug>(what: &str, ours: T, model: T) -> Result<(), String> {
+    if ours == model {
+        Ok(())
+    } else {
+        Err(format!("{what}: ours {ours:?}, model {model:?}"))
+    }
+}
+
+/// Apply one op to both sides, returning what each side produced.
+fn step(ours: &mut LinkedList<u32>, model: &mut Model, op: &Op) -> Result<(), String> {
+    let len = model.deque.len();
+    match op {
+        Op::PushFront(v) => {
+            ours.push_front(*v);
+            model.deque.push_front(*v);
+            model.list.push_front(*v);
+            model.cursor = model.cursor.map(|i| i + 1);
+        }
+        Op::PushBack(v) => {
+            ours.push_back(*v);
+            model.deque.push_back(*v);
+            model.list.push_back(*v);
+        }
+        Op::PopFront => {
+            model.list.pop_front();
+            let expected = model.deque.pop_front();
+            check_eq("pop_front", ours.pop_front(), expected)?;
+            model.cursor = model.cursor.and_then(|i| i.checked_sub(1));
+        }
+        Op::PopBack => {
+            model.list.pop_back();
+            let expected = model.deque.pop_back();
+            check_eq("pop_back", ours.pop_back(), expected)?;
+            model.cursor = model.cursor.filter(|&i| i + 1 < len);
+        }
+        Op::Insert(at, v) => {
+            let at = at % (len + 1);
+            ours.insert(at, *v);
+            model.insert(at, *v);
+            model.cursor = model.cursor.map(|i| if i >= at { i + 1 } else { i });
+        }
+        Op::Remove(at) => {
+            // Deliberately allowed to go one past the end
+            let at = at % (len + 1);
+            let expected = model.remove(at);
+            check_eq("remove", ours.remove(at), expected)?;
+            if expected.is_some() {
+                model.cursor = match model.cursor {
+                    Some(i) if i == at => None,
+                    Some(i) if i > at => Some(i - 1),
+                    other => other,
+                };
+            }
+        }
+        Op::SplitOff(at) => {
+            let at = at % (len + 1);
+            let expected = model.split_off(at);
+            let tail = ours.split_off(at);
+            check_eq("split_off", tail.iter().copied().collect(), expected)?;
+            model.cursor = model.cursor.filter(|&i| i < at);
+        }
+        Op::Append(vs) => {
+            ours.append(&mut list_of(vs));
+            model.insert_all(len, vs);
+        }
+        Op::Prepend(vs) => {
+            ours.prepend(&mut list_of(vs));
+            model.insert_all(0, vs);
+            model.cursor = model.cursor.map(|i| i + vs.len());
+        }
+        Op::Retain(m) => {
+            ours.retain(|x| x % m != 0);
+            model.deque.retain(|x| x % m != 0);
+            model.list = model.list.iter().copied().filter(|x| x % m != 0).collect();
+            model.cursor = None;
+        }
+        Op::Sort => {
+            ours.sort();
+            model.deque.make_contiguous().sort();
+            model.list = model.deque.iter().copied().collect();
+            model.cursor = None;
+        }
+        _ => return cursor_step(ours, model, op),
+    }
+    Ok(())
+}
+
+fn cursor_step(ours: &mut LinkedList<u32>, model: &mut Model, op: &Op) -> Result<(), String> {
+    let len = model.deque.len();
+    let mut cursor = ours.cursor_mut();
+    if let Some(i) = model.cursor {
+        cursor.seek(i);
+    }
+    check_eq("index after seek", cursor.index(), model.cursor)?;
+
+    match op {
+        Op::MoveNext => {
+            cursor.move_next();
+            model.cursor = match model.cursor {
+                None if len > 0 => Some(0),
+                Some(i) if i + 1 < len => Some(i + 1),
+                _ => None,
+            };
+        }
+        Op::MovePrev => {
+            cursor.move_prev();
+            model.cursor = match model.cursor {
+                None if len > 0 => Some(len - 1),
+                Some(i) if i > 0 => Some(i - 1),
+                _ => None,
+            };
+        }
+        Op::Seek(at) => {
+            // Deliberately allowed to go one past the end, onto the ghost
+            let at = at % (len + 1);
+            cursor.seek(at);
+            model.cursor = Some(at).filter(|&i| i < len);
+        }
+        Op::InsertBefore(v) => {
+            cursor.insert_before(*v);
+            match model.cursor {
+                Some(i) => {
+                    model.insert(i, *v);
+                    model.cursor = Some(i + 1);
+                }
+                None => model.insert(len, *v),
+            }
+        }
+        Op::InsertAfter(v) => {
+            cursor.insert_after(*v);
+            match model.cursor {
+                Some(i) => model.insert(i + 1, *v),
+                None => model.insert(0, *v),
+            }
+        }
+        Op::RemoveCurrent => {
+            let removed = cursor.remove_current();
+            let expected = match model.cursor {
+                Some(i) => {
+                    model.cursor = Some(i).filter(|&i| i + 1 < len);
+                    model.remove(i)
+                }
+                None => None,
+            };
+            check_eq("remove_current", removed, expected)?;
+        }
+        Op::SpliceBefore(vs) => {
+            cursor.splice_before(list_of(vs));
+            match model.cursor {
+                Some(i) => {
+                    model.insert_all(i, vs);
+                    model.cursor = Some(i + vs.len());
+                }
+                None => model.insert_all(len, vs),
+            }
+        }
+        Op::SpliceAfter(vs) => {
+            cursor.splice_after(list_of(vs));
+            match model.cursor {
+                Some(i) => model.insert_all(i + 1, vs),
+                None => model.insert_all(0, vs),
+            }
+        }
+        Op::SplitBefore => {
+            let front = cursor.split_before();
+            let at = model.cursor.unwrap_or(len);
+            let mut expected = model.deque.iter().copied().collect::<Vec<_>>();
+            let rest = expected.split_off(at);
+            model.deque = rest.iter().copied().collect();
+            model.list = rest.iter().copied().collect();
+            model.cursor = model.cursor.map(|_| 0);
+            check_eq("split_before", front.iter().copied().collect(), expected)?;
+        }
+        Op::SplitAfter => {
+            let back = cursor.split_after();
+            let at = model.cursor.map_or(0, |i| i + 1);
+            let expected = model.split_off(at);
+            check_eq("split_after", back.iter().copied().collect(), expected)?;
+        }
+        _ => unreachable!("not a cursor op: {op:?}"),
+    }
+
+    check_eq("cursor index", cursor.index(), model.cursor)?;
+    let current = cursor.current().copied();
+    check_eq(
+        "cursor current",
+        current,
+        model.cursor.map(|i| model.deque[i]),
+    )
+}
+
+fn compare(ours: &LinkedList<u32>, model: &Model) -> Result<(), String> {
+    ours.validate().map_err(|err| err.to_string())?;
+    check_eq("len", ours.len(), model.deque.len())?;
+    check_eq("std len", model.list.len(), model.deque.len())?;
+    let expected: Vec<_> = model.deque.iter().copied().collect();
+    check_eq(
+        "std contents",
+        model.list.iter().copied().collect(),
+        expected.clone(),
+    )?;
+    check_eq("contents", ours.iter().copied().collect(), expected.clone())?;
+    check_eq(
+        "reversed contents",
+        ours.iter().rev().copied().collect(),
+        expected.iter().rev().copied().collect::<Vec<_>>(),
+    )?;
+    check_eq("front", ours.front(), expected.first())?;
+    check_eq("back", ours.back(), expected.last())
+}
+
+/// Run `ops` from an empty list, describing the first divergence.
+fn run(ops: &[Op]) -> Result<(), String> {
+    let mut ours = LinkedList::new();
+    let mut model = Model::default();
+    for (i, op) in ops.iter().enumerate() {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            step(&mut ours, &mut model, op).and_then(|_| compare(&ours, &model))
+        }));
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(msg)) => return Err(format!("step {i} ({op:?}): {msg}")),
+            Err(_) => return Err(format!("step {i} ({op:?}): panicked")),
+        }
+    }
+    Ok(())
+}
+
+/// Delta debugging: keep dropping chunks of ops while the run still fails.
+fn shrink(ops: Vec<Op>) -> Vec<Op> {
+    shrink_by(ops, |ops| run(ops).is_err())
+}
+
+fn shrink_by(mut ops: Vec<Op>, fails: impl Fn(&[Op]) -> bool) -> Vec<Op> {
+    let mut chunk = ops.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < ops.len() {
+            let mut candidate = ops.clone();
+            candidate.drain(start..(start + chunk).min(ops.len()));
+            if fails(&candidate) {
+                ops = candidate;
+            } else {
+                start += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+    ops
+}
+
+fn check_seed(seed: u64) {
+    let mut rng = Rng::new(seed);
+    let ops: Vec<_> = (0..OPS_PER_SEED).map(|_| gen_op(&mut rng)).collect();
+    if run(&ops).is_err() {
+        let minimal = shrink(ops);
+        let msg = run(&minimal).unwrap_err();
+        panic!(
+            "seed {seed} diverged, replay with LISTS_DIFF_SEED={seed}\n\
+             {msg}\nminimal ops: {minimal:#?}"
+        );
+    }
+}
+
+#[test]
+fn differential() {
+    match std::env::var("LISTS_DIFF_SEED") {
+        Ok(seed) => check_seed(seed.parse().expect("LISTS_DIFF_SEED is a u64")),
+        Err(_) => (0..SEEDS).for_each(check_seed),
+    }
+}
+
+#[test]
+fn shrink_finds_minimal_sequence() {
+    // Pretend anything containing a `Sort` fails
+    let mut ops: Vec<_> = (0..50).map(Op::PushBack).collect();
+    ops.insert(17, Op::Sort);
+    let minimal = shrink_by(ops, |ops| ops.iter().any(|op| matches!(op, Op::Sort)));
+    assert!(matches!(minimal.as_slice(), [Op::Sort]));
+}
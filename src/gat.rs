@@ -1,29 +1,73 @@
+use std::ops::Deref;
 use std::rc::Rc;
 use std::sync::Arc;
 
-trait RefCountedFamily {
-    type Pointer<T>;
+/// A kind of reference counted pointer, so a structure can be written once
+/// and used with either `Rc` or `Arc`.
+pub trait RefCountedFamily {
+    type Pointer<T>: Deref<Target = T> + Clone;
     fn new<T>(value: T) -> Self::Pointer<T>;
-    // ...
-    // ...
+    /// The value back if this was the only reference to it.
+    fn try_unwrap<T>(this: Self::Pointer<T>) -> Result<T, Self::Pointer<T>>;
+    /// The value back if this was the only reference to it, otherwise just
+    /// let go of it. Unlike a failed `try_unwrap` followed by a drop, when
+    /// several threads let go at once exactly one of them gets the value.
+    fn into_inner<T>(this: Self::Pointer<T>) -> Option<T>;
+    /// Whether both point to the same allocation.
+    fn ptr_eq<T>(a: &Self::Pointer<T>, b: &Self::Pointer<T>) -> bool;
+    /// Mutable access if this is the only reference.
+    fn get_mut<T>(this: &mut Self::Pointer<T>) -> Option<&mut T>;
+    /// Mutable access, cloning the value into a new allocation first if
+    /// it's shared.
+    fn make_mut<T: Clone>(this: &mut Self::Pointer<T>) -> &mut T;
 }
 
-struct RcFamily;
+pub struct RcFamily;
 
 impl RefCountedFamily for RcFamily {
     type Pointer<T> = Rc<T>;
     fn new<T>(value: T) -> Self::Pointer<T> {
         Rc::new(value)
     }
+    fn try_unwrap<T>(this: Self::Pointer<T>) -> Result<T, Self::Pointer<T>> {
+        Rc::try_unwrap(this)
+    }
+    fn into_inner<T>(this: Self::Pointer<T>) -> Option<T> {
+        Rc::into_inner(this)
+    }
+    fn ptr_eq<T>(a: &Self::Pointer<T>, b: &Self::Pointer<T>) -> bool {
+        Rc::ptr_eq(a, b)
+    }
+    fn get_mut<T>(this: &mut Self::Pointer<T>) -> Option<&mut T> {
+        Rc::get_mut(this)
+    }
+    fn make_mut<T: Clone>(this: &mut Self::Pointer<T>) -> &mut T {
+        Rc::make_mut(this)
+    }
 }
 
-struct ArcFamily;
+pub struct ArcFamily;
 
 impl RefCountedFamily for ArcFamily {
     type Pointer<T> = Arc<T>;
     fn new<T>(value: T) -> Self::Pointer<T> {
         Arc::new(value)
     }
+    fn try_unwrap<T>(this: Self::Pointer<T>) -> Result<T, Self::Pointer<T>> {
+        Arc::try_unwrap(this)
+    }
+    fn into_inner<T>(this: Self::Pointer<T>) -> Option<T> {
+        Arc::into_inner(this)
+    }
+    fn ptr_eq<T>(a: &Self::Pointer<T>, b: &Self::Pointer<T>) -> bool {
+        Arc::ptr_eq(a, b)
+    }
+    fn get_mut<T>(this: &mut Self::Pointer<T>) -> Option<&mut T> {
+        Arc::get_mut(this)
+    }
+    fn make_mut<T: Clone>(this: &mut Self::Pointer<T>) -> &mut T {
+        Arc::make_mut(this)
+    }
 }
 
 struct Container<P: RefCountedFamily, T> {
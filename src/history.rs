@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::ptr;
+use std::rc::Rc;
+
+use crate::persistent_stack::List;
+
+/// Undo and redo over versions of some state `S`.
+///
+/// Every version is a `persistent_stack::List` of states, the newest first,
+/// so a version shares everything before it with the version it was
+/// committed on. Committing after an undo doesn't throw the undone states
+/// away, it starts a new branch next to them, and `redo_branch` can go down
+/// any of them. The whole redo tree is kept as its leaves, the `tips`.
+///
+/// Two limits keep it from growing forever. `max_depth` is how many steps
+/// can be undone. `max_states` is how many states are kept: past it, whole
+/// redo branches are dropped, the least recently visited first, then the
+/// states that could be redone from the current one, and then the oldest
+/// undo states. States that can't be reached anymore are only freed once
+/// there are about as many of them as reachable ones, so up to twice the
+/// limits can be held at times. Both limits count states, whatever their
+/// size, so bounding memory means picking `max_states` with the largest
+/// state in mind.
+///
+/// `undo` is O(1). Going forward isn't: the redo tree is only kept as its
+/// tips, so finding what can be redone walks each branch down from its tip
+/// to the current state, O(branches × depth) all together.
+pub struct History<S> {
+    /// The current state first, then everything it can be undone to.
+    /// Always a suffix of the last tip.
+    current: List<Rc<S>>,
+    /// The leaves of the redo tree, least recently visited first
+    tips: Vec<List<Rc<S>>>,
+    /// The shortest `current` may get by undoing
+    floor: usize,
+    /// Distinct states held by `tips`, reachable or not
+    states: usize,
+    max_depth: usize,
+    max_states: usize,
+}
+
+impl<S> History<S> {
+    pub fn new(initial: S) -> Self {
+        Self::with_limits(initial, usize::MAX, usize::MAX)
+    }
+
+    /// `max_states` is a number of states, not of bytes.
+    ///
+    /// # Panics
+    ///
+    /// If `max_states` is zero, there's always at least the current state.
+    pub fn with_limits(initial: S, max_depth: usize, max_states: usize) -> Self {
+        assert!(max_states > 0, "a history has to keep its current state");
+        let current = List::new().prepend(Rc::new(initial));
+        History {
+            tips: vec![current.clone()],
+            current,
+            floor: 1,
+            states: 1,
+            max_depth,
+            max_states,
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        self.current.head().unwrap()
+    }
+
+    /// How many steps `undo` can go back.
+    pub fn undo_depth(&self) -> usize {
+        self.current.len() - self.floor
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.undo_depth() > 0
+    }
+
+    /// Walks the branches like `redo_branches` does.
+    pub fn can_redo(&self) -> bool {
+        self.redos().next().is_some()
+    }
+
+    /// How many states are held, including ones waiting to be freed.
+    pub fn stored_states(&self) -> usize {
+        self.states
+    }
+
+    /// Make `state` the current state, on a new branch if the current one
+    /// has been undone. O(1) until a limit is hit, then pruning compares
+    /// whole branches, O(branches × depth).
+    pub fn commit(&mut self, state: S) {
+        let next = self.current.prepend(Rc::new(state));
+        let last = self.tips.last_mut().unwrap();
+        if last.ptr_eq(&self.current) {
+            *last = next.clone();
+        } else {
+            self.tips.push(next.clone());
+        }
+        self.current = next;
+        self.states += 1;
+        self.floor = self
+            .floor
+            .max(self.current.len().saturating_sub(self.max_depth));
+        self.prune();
+    }
+
+    /// Step back to the previous state, if the depth allows.
+    pub fn undo(&mut self) -> Option<&S> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.current = self.current.tail();
+        Some(self.state())
+    }
+
+    /// Step forward along the branch visited most recently. Costs as much
+    /// as `redo_branch`.
+    pub fn redo(&mut self) -> Option<&S> {
+        self.redo_branch(0)
+    }
+
+    /// The states `redo_branch` can step to, the most recently visited
+    /// branch first. O(branches × depth), every branch is walked from its
+    /// tip.
+    pub fn redo_branches(&self) -> Vec<&S> {
+        self.redos().map(|(_, next)| &**next).collect()
+    }
+
+    /// Step forward to the `n`th of `redo_branches`. Finding it costs as
+    /// much as `redo_branches`, and stepping onto it is O(depth) more, from
+    /// its tip back to here.
+    pub fn redo_branch(&mut self, n: usize) -> Option<&S> {
+        let (tip, _) = self.redos().nth(n)?;
+        let tip = self.tips.remove(tip);
+        self.current = tip.tails().nth(tip.len() - self.current.len() - 1).unwrap();
+        self.tips.push(tip);
+        Some(self.state())
+    }
+
+    /// The tips that go through `current`, each with the state after it.
+    /// Tips on the same branch from there on show up once, the most recent.
+    fn redos(&self) -> impl Iterator<Item = (usize, &Rc<S>)> + '_ {
+        let len = self.current.len();
+        let current = self.current.head().unwrap();
+        // A state's address tells its node apart from any other
+        let mut seen: Vec<*const Rc<S>> = Vec::new();
+        self.tips
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(move |(_, tip)| tip.len() > len)
+            .filter_map(move |(i, tip)| {
+                let mut states = tip.iter().skip(tip.len() - len - 1);
+                let next = states.next().unwrap();
+                if !ptr::eq(states.next().unwrap(), current) || seen.contains(&ptr::from_ref(next))
+                {
+                    return None;
+                }
+                seen.push(next);
+                Some((i, next))
+            })
+    }
+
+    fn prune(&mut self) {
+        // Other branches first, least recently visited first
+        while self.states > self.max_states && self.tips.len() > 1 {
+            let tip = self.tips.remove(0);
+            let shared = self
+                .tips
+                .iter()
+                .map(|other| common_len(&tip, other))
+                .max()
+                .unwrap();
+            self.states -= tip.len() - shared;
+        }
+        // Then what could be redone from here
+        if self.states > self.max_states {
+            let last = self.tips.last_mut().unwrap();
+            self.states -= last.len() - self.current.len();
+            *last = self.current.clone();
+        }
+        // Then the oldest undo states
+        if self.states > self.max_states {
+            self.floor = self.floor.max(self.current.len() + 1 - self.max_states);
+        }
+
+        let unreachable = self.floor - 1;
+        if unreachable > 0 && unreachable >= self.current.len() - unreachable {
+            self.compact();
+        }
+    }
+
+    /// Rebuild every tip that can still be reached without the states below
+    /// the floor. Tips that branched off below it go.
+    fn compact(&mut self) {
+        let root = self
+            .current
+            .tails()
+            .nth(self.current.len() - self.floor)
+            .unwrap();
+        let key = |list: &List<Rc<S>>| ptr::from_ref(list.head().unwrap());
+
+        // Each old state already rebuilt, by the address of its node
+        let mut rebuilt = HashMap::new();
+        rebuilt.insert(
+            key(&root),
+            List::new().prepend(root.head().unwrap().clone()),
+        );
+
+        let tips = std::mem::take(&mut self.tips);
+        for tip in tips {
+            if tip.len() < self.floor {
+                continue;
+            }
+            let mut path: Vec<_> = tip.tails().take(tip.len() - self.floor + 1).collect();
+            if !path.last().unwrap().ptr_eq(&root) {
+                continue;
+            }
+            path.pop();
+            let mut below = rebuilt[&key(&root)].clone();
+            for list in path.iter().rev() {
+                below = rebuilt
+                    .entry(key(list))
+                    .or_insert_with(|| below.prepend(list.head().unwrap().clone()))
+                    .clone();
+            }
+            self.tips.push(below);
+        }
+
+        self.current = rebuilt[&key(&self.current)].clone();
+        self.states = rebuilt.len();
+        self.floor = 1;
+    }
+}
+
+/// How long a suffix two lists share.
+fn common_len<T>(a: &List<T>, b: &List<T>) -> usize {
+    let len = a.len().min(b.len());
+    let a = a.tails().skip(a.len() - len);
+    let b = b.tails().skip(b.len() - len);
+    a.zip(b)
+        .find(|(a, b)| a.ptr_eq(b))
+        .map_or(0, |(a, _)| a.len())
+}
+
+impl<S: Debug> Debug for History<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("History")
+            .field("state", self.state())
+            .field("undo_depth", &self.undo_depth())
+            .field("redo_branches", &self.redo_branches())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::History;
+
+    #[test]
+    fn undo_redo() {
+        let mut history = History::new(0);
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), None);
+
+        history.commit(1);
+        history.commit(2);
+        history.commit(3);
+        assert_eq!(history.state(), &3);
+        assert_eq!(history.undo(), Some(&2));
+        assert_eq!(history.undo(), Some(&1));
+        assert_eq!(history.redo(), Some(&2));
+        assert_eq!(history.redo(), Some(&3));
+        assert_eq!(history.redo(), None);
+        assert!(!history.can_redo());
+
+        for state in [2, 1, 0] {
+            assert_eq!(history.undo(), Some(&state));
+        }
+        assert!(!history.can_undo());
+        assert_eq!(history.stored_states(), 4);
+    }
+
+    #[test]
+    fn branches() {
+        let mut history = History::new("a");
+        history.commit("b");
+        history.commit("c");
+        history.undo();
+        history.undo();
+
+        // Committing after an undo keeps the old branch around
+        history.commit("d");
+        history.commit("e");
+        assert!(!history.can_redo());
+        history.undo();
+        history.undo();
+        assert_eq!(history.state(), &"a");
+        assert_eq!(history.redo_branches(), [&"d", &"b"]);
+
+        assert_eq!(history.redo_branch(1), Some(&"b"));
+        assert_eq!(history.redo(), Some(&"c"));
+        history.undo();
+        history.undo();
+        // "b" is now the most recently visited
+        assert_eq!(history.redo_branches(), [&"b", &"d"]);
+        assert_eq!(history.redo_branch(1), Some(&"d"));
+        assert_eq!(history.redo(), Some(&"e"));
+        assert_eq!(history.redo_branch(1), None);
+        assert_eq!(history.stored_states(), 5);
+
+        // A commit in the middle of a branch starts another one
+        history.undo();
+        history.commit("f");
+        history.undo();
+        assert_eq!(history.redo_branches(), [&"f", &"e"]);
+    }
+
+    #[test]
+    fn max_depth() {
+        let mut history = History::with_limits(0, 3, usize::MAX);
+        for state in 1..=10 {
+            history.commit(state);
+        }
+        assert_eq!(history.undo_depth(), 3);
+        for state in [9, 8, 7] {
+            assert_eq!(history.undo(), Some(&state));
+        }
+        assert_eq!(history.undo(), None);
+
+        // Undoing doesn't bring back what's past the limit
+        history.commit(11);
+        assert_eq!(history.undo_depth(), 1);
+        assert_eq!(history.undo(), Some(&7));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo_branches(), [&11, &8]);
+    }
+
+    #[test]
+    fn max_states() {
+        let mut history = History::with_limits(0, usize::MAX, 6);
+        history.commit(1);
+        history.commit(2);
+        history.undo();
+        history.commit(3);
+        history.undo();
+        history.commit(4);
+        assert_eq!(history.stored_states(), 5);
+        history.undo();
+        assert_eq!(history.redo_branches(), [&4, &3, &2]);
+
+        // The branch to 2 was visited longest ago, so it goes first
+        history.commit(5);
+        history.commit(6);
+        assert_eq!(history.stored_states(), 6);
+        history.undo();
+        history.undo();
+        assert_eq!(history.redo_branches(), [&5, &4, &3]);
+
+        // Down to a single branch, then what could be redone goes
+        history.redo();
+        history.undo();
+        history.commit(7);
+        history.commit(8);
+        history.commit(9);
+        assert!(!history.can_redo());
+        assert_eq!(history.state(), &9);
+
+        // Then the oldest undo states
+        for state in 10..100 {
+            history.commit(state);
+            assert!(history.stored_states() <= 12);
+        }
+        while history.undo().is_some() {}
+        assert_eq!(history.state(), &94);
+    }
+
+    #[test]
+    fn long() {
+        let mut history = History::with_limits(0, 100, usize::MAX);
+        for state in 1..100_000 {
+            history.commit(state);
+            if state % 1000 == 0 {
+                // Leave a branch behind every so often
+                history.undo();
+                history.commit(-state);
+            }
+            assert!(history.stored_states() <= 250);
+        }
+        while history.undo().is_some() {}
+        assert_eq!(history.state(), &99_899);
+    }
+}
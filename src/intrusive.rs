@@ -0,0 +1,832 @@
+//! A doubly linked list whose links live inside the elements themselves.
+//!
+//! Elements are borrowed for the list's lifetime `'a` rather than owned, so
+//! they can sit in an arena, a `Vec`, a `Box` or on the stack, and linking
+//! them never allocates. An element embeds one `Links` field per list it can
+//! be in, and an `Adapter` tells the list which field to use:
+//!
+//! ```
+//! use lists::intrusive::{Adapter, Links, List};
+//!
+//! struct Timer {
+//!     deadline: u64,
+//!     links: Links<Timer>,
+//! }
+//!
+//! struct TimerAdapter;
+//!
+//! unsafe impl Adapter for TimerAdapter {
+//!     type Elem = Timer;
+//!
+//!     fn links(timer: &Timer) -> &Links<Timer> {
+//!         &timer.links
+//!     }
+//! }
+//!
+//! let timers: Vec<_> = [30, 10, 20]
+//!     .map(|deadline| Timer { deadline, links: Links::new() })
+//!     .into();
+//!
+//! let mut list = List::<TimerAdapter>::new();
+//! for timer in &timers {
+//!     list.push_back(timer);
+//! }
+//! let deadlines: Vec<_> = list.iter().map(|t| t.deadline).collect();
+//! assert_eq!(deadlines, [30, 10, 20]);
+//! ```
+//!
+//! Because the list only ever sees shared references, any mutable state in
+//! an element has to sit behind a `Cell` or similar, just like `Links` does.
+
+use std::cell::Cell;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+type Link<T> = Option<NonNull<T>>;
+
+/// The link fields an element embeds to be put in a `List`.
+pub struct Links<T> {
+    /// front表示前一个元素
+    front: Cell<Link<T>>,
+    /// back表示后一个元素
+    back: Cell<Link<T>>,
+    linked: Cell<bool>,
+}
+
+impl<T> Links<T> {
+    pub const fn new() -> Self {
+        Links {
+            front: Cell::new(None),
+            back: Cell::new(None),
+            linked: Cell::new(false),
+        }
+    }
+
+    /// Whether the element is currently in a list through these links.
+    pub fn is_linked(&self) -> bool {
+        self.linked.get()
+    }
+}
+
+impl<T> Default for Links<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for Links<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Links")
+            .field("linked", &self.is_linked())
+            .finish()
+    }
+}
+
+/// Maps an element to the `Links` a particular kind of list threads through.
+///
+/// # Safety
+///
+/// `links` must return the same field of `elem` every time it is called, and
+/// no other adapter may hand out that same field.
+pub unsafe trait Adapter {
+    type Elem;
+
+    fn links(elem: &Self::Elem) -> &Links<Self::Elem>;
+}
+
+pub struct List<'a, A: Adapter> {
+    /// front表示最前一个元素
+    front: Link<A::Elem>,
+    /// back表示最后一个元素
+    back: Link<A::Elem>,
+    len: usize,
+    _p: PhantomData<&'a A::Elem>,
+}
+
+/// The element behind a node pointer.
+///
+/// # Safety
+///
+/// `node` must have been linked from a `&'b` reference that is still live.
+unsafe fn elem<'b, T>(node: NonNull<T>) -> &'b T {
+    &*node.as_ptr()
+}
+
+/// The links of the element behind a node pointer.
+///
+/// # Safety
+///
+/// Same as `elem`.
+unsafe fn links<'b, A: Adapter>(node: NonNull<A::Elem>) -> &'b Links<A::Elem> {
+    A::links(elem(node))
+}
+
+impl<'a, A: Adapter> List<'a, A> {
+    pub fn new() -> Self {
+        List {
+            front: None,
+            back: None,
+            len: 0,
+            _p: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<&'a A::Elem> {
+        unsafe { self.front.map(|node| elem(node)) }
+    }
+
+    pub fn back(&self) -> Option<&'a A::Elem> {
+        unsafe { self.back.map(|node| elem(node)) }
+    }
+
+    /// # Panics
+    ///
+    /// If `elem` is already linked into a list through this adapter.
+    pub fn push_front(&mut self, elem: &'a A::Elem) {
+        // The ghost's "after" is the front of the list
+        self.cursor_mut().insert_after(elem);
+    }
+
+    /// # Panics
+    ///
+    /// If `elem` is already linked into a list through this adapter.
+    pub fn push_back(&mut self, elem: &'a A::Elem) {
+        // The ghost's "before" is the back of the list
+        self.cursor_mut().insert_before(elem);
+    }
+
+    pub fn pop_front(&mut self) -> Option<&'a A::Elem> {
+        self.cursor_front_mut().remove_current()
+    }
+
+    pub fn pop_back(&mut self) -> Option<&'a A::Elem> {
+        self.cursor_back_mut().remove_current()
+    }
+
+    /// Unlink `elem` from wherever it is in the list, without searching.
+    ///
+    /// # Safety
+    ///
+    /// `elem` must currently be linked into this very list.
+    pub unsafe fn remove(&mut self, elem: &'a A::Elem) {
+        self.unlink_node(NonNull::from(elem));
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, 'a, A> {
+        Iter {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            _p: PhantomData,
+        }
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, 'a, A> {
+        Cursor {
+            cur: self.front,
+            list: self,
+            index: self.front.map(|_| 0),
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, 'a, A> {
+        Cursor {
+            cur: self.back,
+            list: self,
+            index: self.back.map(|_| self.len - 1),
+        }
+    }
+
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, 'a, A> {
+        CursorMut {
+            cur: None,
+            list: self,
+            index: None,
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, 'a, A> {
+        CursorMut {
+            cur: self.front,
+            index: self.front.map(|_| 0),
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, 'a, A> {
+        CursorMut {
+            cur: self.back,
+            index: self.back.map(|_| self.len - 1),
+            list: self,
+        }
+    }
+
+    /// Link `elem` in between `prev` and `next`, which must be neighbours
+    /// in this list (or `None` for the ends).
+    fn link_node(&mut self, elem: &'a A::Elem, prev: Link<A::Elem>, next: Link<A::Elem>) {
+        let node_links = A::links(elem);
+        assert!(
+            !node_links.is_linked(),
+            "element is already linked into a list"
+        );
+        let node = NonNull::from(elem);
+        node_links.linked.set(true);
+        node_links.front.set(prev);
+        node_links.back.set(next);
+        unsafe {
+            match prev {
+                Some(prev) => links::<A>(prev).back.set(Some(node)),
+                None => self.front = Some(node),
+            }
+            match next {
+                Some(next) => links::<A>(next).front.set(Some(node)),
+                None => self.back = Some(node),
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Detach `node` from the list, leaving its links cleared.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be linked into this list.
+    unsafe fn unlink_node(&mut self, node: NonNull<A::Elem>) {
+        let node_links = links::<A>(node);
+        let prev = node_links.front.take();
+        let next = node_links.back.take();
+        node_links.linked.set(false);
+
+        match prev {
+            Some(prev) => links::<A>(prev).back.set(next),
+            None => self.front = next,
+        }
+        match next {
+            Some(next) => links::<A>(next).front.set(prev),
+            None => self.back = prev,
+        }
+        self.len -= 1;
+    }
+
+    /// Move all of `input` in between `prev` and `next`, which must be
+    /// neighbours in this list (or `None` for the ends).
+    fn splice_between(&mut self, mut input: Self, prev: Link<A::Elem>, next: Link<A::Elem>) {
+        let (Some(in_front), Some(in_back)) = (input.front.take(), input.back.take()) else {
+            return;
+        };
+        unsafe {
+            links::<A>(in_front).front.set(prev);
+            links::<A>(in_back).back.set(next);
+            match prev {
+                Some(prev) => links::<A>(prev).back.set(Some(in_front)),
+                None => self.front = Some(in_front),
+            }
+            match next {
+                Some(next) => links::<A>(next).front.set(Some(in_back)),
+                None => self.back = Some(in_back),
+            }
+        }
+        self.len += input.len;
+        // Input dropped here, with nothing left to unlink
+        input.len = 0;
+    }
+}
+
+impl<'a, A: Adapter> Drop for List<'a, A> {
+    fn drop(&mut self) {
+        // Unlink everything so the elements can go into another list
+        self.clear();
+    }
+}
+
+impl<'a, A: Adapter> Default for List<'a, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, A: Adapter> Debug for List<'a, A>
+where
+    A::Elem: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'l, 'a, A: Adapter> IntoIterator for &'l List<'a, A> {
+    type Item = &'a A::Elem;
+    type IntoIter = Iter<'l, 'a, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'l, 'a, A: Adapter> {
+    front: Link<A::Elem>,
+    back: Link<A::Elem>,
+    len: usize,
+    _p: PhantomData<&'l List<'a, A>>,
+}
+
+impl<'l, 'a, A: Adapter> Iterator for Iter<'l, 'a, A> {
+    type Item = &'a A::Elem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| unsafe {
+            self.len -= 1;
+            self.front = links::<A>(node).back.get();
+            elem(node)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'l, 'a, A: Adapter> DoubleEndedIterator for Iter<'l, 'a, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| unsafe {
+            self.len -= 1;
+            self.back = links::<A>(node).front.get();
+            elem(node)
+        })
+    }
+}
+
+impl<'l, 'a, A: Adapter> ExactSizeIterator for Iter<'l, 'a, A> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+pub struct Cursor<'l, 'a, A: Adapter> {
+    cur: Link<A::Elem>,
+    list: &'l List<'a, A>,
+    index: Option<usize>,
+}
+
+pub struct CursorMut<'l, 'a, A: Adapter> {
+    cur: Link<A::Elem>,
+    list: &'l mut List<'a, A>,
+    index: Option<usize>,
+}
+
+impl<'l, 'a, A: Adapter> Clone for Cursor<'l, 'a, A> {
+    fn clone(&self) -> Self {
+        Cursor {
+            cur: self.cur,
+            list: self.list,
+            index: self.index,
+        }
+    }
+}
+
+/// Step `cur` one element towards the back, through the ghost.
+fn step_next<A: Adapter>(list: &List<'_, A>, cur: &mut Link<A::Elem>, index: &mut Option<usize>) {
+    if let Some(node) = *cur {
+        *cur = unsafe { links::<A>(node).back.get() };
+        // Walking off the back lands on the ghost, which has no index
+        *index = cur.and(index.map(|i| i + 1));
+    } else if let Some(front) = list.front {
+        *cur = Some(front);
+        *index = Some(0);
+    }
+}
+
+/// Step `cur` one element towards the front, through the ghost.
+fn step_prev<A: Adapter>(list: &List<'_, A>, cur: &mut Link<A::Elem>, index: &mut Option<usize>) {
+    if let Some(node) = *cur {
+        *cur = unsafe { links::<A>(node).front.get() };
+        *index = cur.and(index.map(|i| i - 1));
+    } else if let Some(back) = list.back {
+        *cur = Some(back);
+        *index = Some(list.len - 1);
+    }
+}
+
+/// The neighbour of `cur` on the given side; the ghost's neighbours are the
+/// ends of the list.
+fn neighbour<'a, A: Adapter>(
+    list: &List<'a, A>,
+    cur: Link<A::Elem>,
+    towards_back: bool,
+) -> Option<&'a A::Elem> {
+    unsafe {
+        let node = match (cur, towards_back) {
+            (Some(cur), true) => links::<A>(cur).back.get(),
+            (Some(cur), false) => links::<A>(cur).front.get(),
+            (None, true) => list.front,
+            (None, false) => list.back,
+        };
+        node.map(|node| elem(node))
+    }
+}
+
+impl<'l, 'a, A: Adapter> Cursor<'l, 'a, A> {
+    pub fn move_next(&mut self) {
+        step_next(self.list, &mut self.cur, &mut self.index);
+    }
+
+    pub fn move_prev(&mut self) {
+        step_prev(self.list, &mut self.cur, &mut self.index);
+    }
+
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn current(&self) -> Option<&'a A::Elem> {
+        unsafe { self.cur.map(|node| elem(node)) }
+    }
+
+    pub fn peek_next(&self) -> Option<&'a A::Elem> {
+        neighbour(self.list, self.cur, true)
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a A::Elem> {
+        neighbour(self.list, self.cur, false)
+    }
+}
+
+impl<'l, 'a, A: Adapter> CursorMut<'l, 'a, A> {
+    pub fn move_next(&mut self) {
+        step_next(self.list, &mut self.cur, &mut self.index);
+    }
+
+    pub fn move_prev(&mut self) {
+        step_prev(self.list, &mut self.cur, &mut self.index);
+    }
+
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn as_cursor(&self) -> Cursor<'_, 'a, A> {
+        Cursor {
+            cur: self.cur,
+            list: self.list,
+            index: self.index,
+        }
+    }
+
+    pub fn current(&self) -> Option<&'a A::Elem> {
+        unsafe { self.cur.map(|node| elem(node)) }
+    }
+
+    pub fn peek_next(&self) -> Option<&'a A::Elem> {
+        neighbour(self.list, self.cur, true)
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a A::Elem> {
+        neighbour(self.list, self.cur, false)
+    }
+
+    /// Link `elem` in before the cursor, or at the back on the ghost.
+    ///
+    /// # Panics
+    ///
+    /// If `elem` is already linked into a list through this adapter.
+    pub fn insert_before(&mut self, elem: &'a A::Elem) {
+        match self.cur {
+            Some(cur) => {
+                let prev = unsafe { links::<A>(cur).front.get() };
+                self.list.link_node(elem, prev, Some(cur));
+                // Index moves forward by one
+                *self.index.as_mut().unwrap() += 1;
+            }
+            None => self.list.link_node(elem, self.list.back, None),
+        }
+    }
+
+    /// Link `elem` in after the cursor, or at the front on the ghost.
+    ///
+    /// # Panics
+    ///
+    /// If `elem` is already linked into a list through this adapter.
+    pub fn insert_after(&mut self, elem: &'a A::Elem) {
+        match self.cur {
+            Some(cur) => {
+                let next = unsafe { links::<A>(cur).back.get() };
+                self.list.link_node(elem, Some(cur), next);
+            }
+            None => self.list.link_node(elem, None, self.list.front),
+        }
+    }
+
+    /// Unlink the current element and move on to the next one, or onto the
+    /// ghost if it was the back.
+    pub fn remove_current(&mut self) -> Option<&'a A::Elem> {
+        let cur = self.cur?;
+        unsafe {
+            let next = links::<A>(cur).back.get();
+            self.list.unlink_node(cur);
+            if next.is_none() {
+                self.index = None;
+            }
+            self.cur = next;
+            Some(elem(cur))
+        }
+    }
+
+    pub fn split_before(&mut self) -> List<'a, A> {
+        let Some(cur) = self.cur else {
+            // We're at the ghost, the whole list goes
+            return std::mem::take(self.list);
+        };
+        unsafe {
+            let prev = links::<A>(cur).front.get();
+            let output = List {
+                front: prev.and(self.list.front),
+                back: prev,
+                len: self.index.unwrap(),
+                _p: PhantomData,
+            };
+            if let Some(prev) = prev {
+                links::<A>(prev).back.set(None);
+                links::<A>(cur).front.set(None);
+            }
+            self.list.front = Some(cur);
+            self.list.len -= output.len;
+            self.index = Some(0);
+            output
+        }
+    }
+
+    pub fn split_after(&mut self) -> List<'a, A> {
+        let Some(cur) = self.cur else {
+            // We're at the ghost, the whole list goes
+            return std::mem::take(self.list);
+        };
+        unsafe {
+            let next = links::<A>(cur).back.get();
+            let new_len = self.index.unwrap() + 1;
+            let output = List {
+                front: next,
+                back: next.and(self.list.back),
+                len: self.list.len - new_len,
+                _p: PhantomData,
+            };
+            if let Some(next) = next {
+                links::<A>(next).front.set(None);
+                links::<A>(cur).back.set(None);
+            }
+            self.list.back = Some(cur);
+            self.list.len = new_len;
+            output
+        }
+    }
+
+    /// Move all of `input` in before the cursor, or at the back on the ghost.
+    pub fn splice_before(&mut self, input: List<'a, A>) {
+        match self.cur {
+            Some(cur) => {
+                let prev = unsafe { links::<A>(cur).front.get() };
+                // Index moves forward by input length
+                *self.index.as_mut().unwrap() += input.len;
+                self.list.splice_between(input, prev, Some(cur));
+            }
+            None => {
+                let back = self.list.back;
+                self.list.splice_between(input, back, None);
+            }
+        }
+    }
+
+    /// Move all of `input` in after the cursor, or at the front on the ghost.
+    pub fn splice_after(&mut self, input: List<'a, A>) {
+        match self.cur {
+            Some(cur) => {
+                let next = unsafe { links::<A>(cur).back.get() };
+                self.list.splice_between(input, Some(cur), next);
+            }
+            None => {
+                let front = self.list.front;
+                self.list.splice_between(input, None, front);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Adapter, Links, List};
+    use std::cell::Cell;
+
+    struct Task {
+        id: u32,
+        runs: Cell<u32>,
+        ready: Links<Task>,
+        all: Links<Task>,
+    }
+
+    impl Task {
+        fn new(id: u32) -> Self {
+            Task {
+                id,
+                runs: Cell::new(0),
+                ready: Links::new(),
+                all: Links::new(),
+            }
+        }
+    }
+
+    struct Ready;
+
+    unsafe impl Adapter for Ready {
+        type Elem = Task;
+
+        fn links(task: &Task) -> &Links<Task> {
+            &task.ready
+        }
+    }
+
+    struct All;
+
+    unsafe impl Adapter for All {
+        type Elem = Task;
+
+        fn links(task: &Task) -> &Links<Task> {
+            &task.all
+        }
+    }
+
+    fn ids<A: Adapter<Elem = Task>>(list: &List<'_, A>) -> Vec<u32> {
+        let ids: Vec<_> = list.iter().map(|t| t.id).collect();
+        let mut rev: Vec<_> = list.iter().rev().map(|t| t.id).collect();
+        rev.reverse();
+        assert_eq!(ids, rev);
+        assert_eq!(ids.len(), list.len());
+        ids
+    }
+
+    #[test]
+    fn basics() {
+        let tasks: Vec<_> = (0..5).map(Task::new).collect();
+        let mut list = List::<Ready>::new();
+        assert!(list.pop_front().is_none());
+
+        list.push_back(&tasks[1]);
+        list.push_back(&tasks[2]);
+        list.push_front(&tasks[0]);
+        assert_eq!(ids(&list), [0, 1, 2]);
+        assert!(tasks[1].ready.is_linked());
+        assert!(!tasks[3].ready.is_linked());
+
+        assert_eq!(list.pop_front().map(|t| t.id), Some(0));
+        assert_eq!(list.pop_back().map(|t| t.id), Some(2));
+        assert!(!tasks[0].ready.is_linked());
+        assert_eq!(ids(&list), [1]);
+
+        // Popped elements can go straight back in
+        list.push_back(&tasks[0]);
+        list.push_back(&tasks[2]);
+        unsafe { list.remove(&tasks[0]) };
+        assert_eq!(ids(&list), [1, 2]);
+        assert_eq!(list.front().map(|t| t.id), Some(1));
+        assert_eq!(list.back().map(|t| t.id), Some(2));
+
+        // Dropping the list unlinks everything
+        drop(list);
+        assert!(tasks.iter().all(|t| !t.ready.is_linked()));
+    }
+
+    #[test]
+    fn two_lists_one_element() {
+        let tasks: Vec<_> = (0..4).map(Task::new).collect();
+        let mut all = List::<All>::new();
+        let mut ready = List::<Ready>::new();
+        for task in &tasks {
+            all.push_back(task);
+        }
+        ready.push_back(&tasks[3]);
+        ready.push_back(&tasks[1]);
+
+        // Run whatever is ready
+        while let Some(task) = ready.pop_front() {
+            task.runs.set(task.runs.get() + 1);
+        }
+        let runs: Vec<_> = all.iter().map(|t| t.runs.get()).collect();
+        assert_eq!(runs, [0, 1, 0, 1]);
+        assert_eq!(ids(&all), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn boxed_elements() {
+        let tasks: Vec<Box<Task>> = (0..3).map(|id| Box::new(Task::new(id))).collect();
+        let mut list = List::<Ready>::new();
+        for task in tasks.iter().rev() {
+            list.push_back(task);
+        }
+        assert_eq!(ids(&list), [2, 1, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "already linked")]
+    fn double_link() {
+        let task = Task::new(0);
+        let mut a = List::<Ready>::new();
+        let mut b = List::<Ready>::new();
+        a.push_back(&task);
+        b.push_back(&task);
+    }
+
+    #[test]
+    fn cursor() {
+        let tasks: Vec<_> = (0..6).map(Task::new).collect();
+        let mut list = List::<Ready>::new();
+        for task in &tasks[1..4] {
+            list.push_back(task);
+        }
+
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_next();
+        assert_eq!(cursor.current().map(|t| t.id), Some(2));
+        assert_eq!(cursor.peek_prev().map(|t| t.id), Some(1));
+        assert_eq!(cursor.peek_next().map(|t| t.id), Some(3));
+
+        cursor.insert_before(&tasks[4]);
+        cursor.insert_after(&tasks[5]);
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.as_cursor().current().map(|t| t.id), Some(2));
+        assert_eq!(ids(&list), [1, 4, 2, 5, 3]);
+
+        let mut cursor = list.cursor_mut();
+        cursor.insert_after(&tasks[0]);
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(5));
+        assert_eq!(cursor.remove_current().map(|t| t.id), Some(3));
+        assert_eq!(cursor.current().map(|t| t.id), None);
+        cursor.move_next();
+        assert_eq!(cursor.current().map(|t| t.id), Some(0));
+        assert_eq!(ids(&list), [0, 1, 4, 2, 5]);
+
+        let cursor = list.cursor_back();
+        assert_eq!(cursor.index(), Some(4));
+        assert_eq!(cursor.current().map(|t| t.id), Some(5));
+    }
+
+    #[test]
+    fn cursor_split_splice() {
+        let tasks: Vec<_> = (0..6).map(Task::new).collect();
+        let mut list = List::<Ready>::new();
+        for task in &tasks {
+            list.push_back(task);
+        }
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let front = cursor.split_before();
+        assert_eq!(cursor.index(), Some(0));
+        let back = cursor.split_after();
+        assert_eq!(ids(&front), [0, 1]);
+        assert_eq!(ids(&back), [3, 4, 5]);
+        assert_eq!(ids(&list), [2]);
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_before(back);
+        assert_eq!(cursor.index(), Some(3));
+        cursor.splice_after(front);
+        assert_eq!(ids(&list), [3, 4, 5, 2, 0, 1]);
+
+        // Splitting at the ends leaves one side empty
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(ids(&cursor.split_before()), []);
+        let mut cursor = list.cursor_back_mut();
+        assert_eq!(ids(&cursor.split_after()), []);
+
+        // On the ghost everything moves
+        let mut cursor = list.cursor_mut();
+        let all = cursor.split_after();
+        assert!(list.is_empty());
+        let mut cursor = list.cursor_mut();
+        cursor.splice_after(all);
+        assert_eq!(ids(&list), [3, 4, 5, 2, 0, 1]);
+        assert!(tasks.iter().all(|t| t.ready.is_linked()));
+    }
+}
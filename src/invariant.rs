@@ -0,0 +1,85 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// A broken structural invariant found by one of the `validate` methods.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantError {
+    /// Exactly one of the two end pointers is null.
+    EndsDisagree,
+    /// Following the forward links loops back onto an earlier node.
+    Cycle,
+    /// The node at `index` doesn't link back to the node before it.
+    BrokenBackLink { index: usize },
+    /// Walking forward from the front doesn't end on the back pointer.
+    BackNotLast,
+    /// The stored length doesn't match the number of nodes.
+    LenMismatch { len: usize, counted: usize },
+}
+
+impl Display for InvariantError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::EndsDisagree => write!(f, "only one end pointer is set"),
+            InvariantError::Cycle => write!(f, "forward links contain a cycle"),
+            InvariantError::BrokenBackLink { index } => {
+                write!(f, "node {index} doesn't link back to its predecessor")
+            }
+            InvariantError::BackNotLast => write!(f, "back pointer isn't the last node"),
+            InvariantError::LenMismatch { len, counted } => {
+                write!(f, "len is {len} but there are {counted} nodes")
+            }
+        }
+    }
+}
+
+impl Error for InvariantError {}
+
+/// Brent's cycle detection over `next`, starting from `start`.
+pub(crate) fn has_cycle<P, F>(start: Option<P>, mut next: F) -> bool
+where
+    P: Copy + Eq,
+    F: FnMut(P) -> Option<P>,
+{
+    let Some(mut tortoise) = start else {
+        return false;
+    };
+    let mut hare = next(tortoise);
+    let mut power = 1;
+    let mut lambda = 1;
+    while let Some(h) = hare {
+        if h == tortoise {
+            return true;
+        }
+        if power == lambda {
+            // Teleport the tortoise and give the hare twice as long
+            tortoise = h;
+            power *= 2;
+            lambda = 0;
+        }
+        hare = next(h);
+        lambda += 1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::has_cycle;
+
+    #[test]
+    fn cycles() {
+        // next[i] is the successor of i
+        let chain = |next: &'static [Option<usize>]| move |i: usize| next[i];
+
+        assert!(!has_cycle(None, chain(&[])));
+        assert!(!has_cycle(Some(0), chain(&[None])));
+        assert!(has_cycle(Some(0), chain(&[Some(0)])));
+        assert!(!has_cycle(Some(0), chain(&[Some(1), Some(2), None])));
+        assert!(has_cycle(
+            Some(0),
+            chain(&[Some(1), Some(2), Some(3), Some(4), Some(2)])
+        ));
+        assert!(has_cycle(Some(0), chain(&[Some(1), Some(0)])));
+    }
+}
@@ -4,5 +4,18 @@ pub mod a_bad_stack;
 pub mod a_production_linked_list;
 pub mod an_ok_singly_linked_stack;
 pub mod an_ok_unsafe_queue;
+#[cfg(test)]
+mod differential;
 pub mod gat;
+pub mod history;
+pub mod intrusive;
+pub mod invariant;
+pub mod persistent_deque;
+pub mod persistent_queue;
+pub mod persistent_ral;
 pub mod persistent_stack;
+pub mod stream;
+pub mod sync;
+#[cfg(test)]
+mod testing;
+pub mod zipper;
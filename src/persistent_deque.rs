@@ -0,0 +1,638 @@
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+
+use crate::gat::{RcFamily, RefCountedFamily};
+
+type Ptr<P, X> = <P as RefCountedFamily>::Pointer<X>;
+
+/// A persistent deque on a 2-3 finger tree (Hinze and Paterson), annotated
+/// with sizes.
+///
+/// Pushing and popping at either end is amortized O(1) as long as each
+/// version is only built on once, the way an ephemeral deque would be used.
+/// The tree isn't lazy, so pushing onto the same old version over and over
+/// can cost O(log n) every time, and that is the worst case. `concat`,
+/// `split_at` and `get` are O(log n). Every operation returns a new version
+/// that shares all the untouched nodes with the old one.
+///
+/// Each element sits in its own `P` pointer, so it costs an allocation of
+/// its own on top of the tree's nodes.
+pub struct Deque<T, P: RefCountedFamily = RcFamily> {
+    tree: Tree<T, P>,
+}
+
+/// What the tree holds at each depth: the deque's own elements at the top,
+/// 2-3 nodes of the level above further down. Rust can't express the usual
+/// `FingerTree<Node<T>>` nesting without monomorphizing forever, so every
+/// level uses this one type instead.
+enum Elem<T, P: RefCountedFamily> {
+    Leaf(Ptr<P, T>),
+    Node(Ptr<P, Node<T, P>>),
+}
+
+struct Node<T, P: RefCountedFamily> {
+    size: usize,
+    /// Two or three of them
+    children: Vec<Elem<T, P>>,
+}
+
+/// One to four elements at an end of a `Deep` tree
+type Digit<T, P> = Vec<Elem<T, P>>;
+
+enum Tree<T, P: RefCountedFamily> {
+    Empty,
+    Single(Elem<T, P>),
+    Deep(Ptr<P, Deep<T, P>>),
+}
+
+struct Deep<T, P: RefCountedFamily> {
+    size: usize,
+    prefix: Digit<T, P>,
+    middle: Tree<T, P>,
+    suffix: Digit<T, P>,
+}
+
+fn digit_size<T, P: RefCountedFamily>(digit: &[Elem<T, P>]) -> usize {
+    digit.iter().map(Elem::size).sum()
+}
+
+/// Which of `elems` holds position `i`, and the position within it.
+fn locate<T, P: RefCountedFamily>(elems: &[Elem<T, P>], mut i: usize) -> (usize, usize) {
+    for (j, elem) in elems.iter().enumerate() {
+        if i < elem.size() {
+            return (j, i);
+        }
+        i -= elem.size();
+    }
+    unreachable!("position past the end of the elements")
+}
+
+/// Group 2 to 12 elements into 2-3 nodes.
+fn nodes<T, P: RefCountedFamily>(elems: &[Elem<T, P>]) -> Vec<Elem<T, P>> {
+    let mut out = Vec::with_capacity(4);
+    let mut rest = elems;
+    loop {
+        match rest.len() {
+            2 | 3 => {
+                out.push(Elem::node(rest.to_vec()));
+                return out;
+            }
+            4 => {
+                out.push(Elem::node(rest[..2].to_vec()));
+                out.push(Elem::node(rest[2..].to_vec()));
+                return out;
+            }
+            _ => {
+                out.push(Elem::node(rest[..3].to_vec()));
+                rest = &rest[3..];
+            }
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Elem<T, P> {
+    fn node(children: Vec<Self>) -> Self {
+        let size = digit_size(&children);
+        Elem::Node(P::new(Node { size, children }))
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Elem::Leaf(_) => 1,
+            Elem::Node(node) => node.size,
+        }
+    }
+
+    fn children(&self) -> &[Self] {
+        match self {
+            Elem::Node(node) => &node.children,
+            Elem::Leaf(_) => unreachable!("leaves only live at the top level"),
+        }
+    }
+
+    fn lookup(&self, i: usize) -> &T {
+        match self {
+            Elem::Leaf(elem) => elem,
+            Elem::Node(node) => {
+                let (j, i) = locate(&node.children, i);
+                node.children[j].lookup(i)
+            }
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Tree<T, P> {
+    fn deep(prefix: Digit<T, P>, middle: Self, suffix: Digit<T, P>) -> Self {
+        let size = digit_size(&prefix) + middle.size() + digit_size(&suffix);
+        Tree::Deep(P::new(Deep {
+            size,
+            prefix,
+            middle,
+            suffix,
+        }))
+    }
+
+    fn from_digit(digit: &[Elem<T, P>]) -> Self {
+        digit
+            .iter()
+            .fold(Tree::Empty, |tree, elem| tree.push_back(elem.clone()))
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Tree::Empty => 0,
+            Tree::Single(elem) => elem.size(),
+            Tree::Deep(deep) => deep.size,
+        }
+    }
+
+    fn push_front(&self, elem: Elem<T, P>) -> Self {
+        match self {
+            Tree::Empty => Tree::Single(elem),
+            Tree::Single(other) => Tree::deep(vec![elem], Tree::Empty, vec![other.clone()]),
+            Tree::Deep(deep) if deep.prefix.len() == 4 => {
+                // Full, push three of them down a level as one node
+                let node = Elem::node(deep.prefix[1..].to_vec());
+                Tree::deep(
+                    vec![elem, deep.prefix[0].clone()],
+                    deep.middle.push_front(node),
+                    deep.suffix.clone(),
+                )
+            }
+            Tree::Deep(deep) => {
+                let mut prefix = Vec::with_capacity(deep.prefix.len() + 1);
+                prefix.push(elem);
+                prefix.extend(deep.prefix.iter().cloned());
+                Tree::deep(prefix, deep.middle.clone(), deep.suffix.clone())
+            }
+        }
+    }
+
+    fn push_back(&self, elem: Elem<T, P>) -> Self {
+        match self {
+            Tree::Empty => Tree::Single(elem),
+            Tree::Single(other) => Tree::deep(vec![other.clone()], Tree::Empty, vec![elem]),
+            Tree::Deep(deep) if deep.suffix.len() == 4 => {
+                let node = Elem::node(deep.suffix[..3].to_vec());
+                Tree::deep(
+                    deep.prefix.clone(),
+                    deep.middle.push_back(node),
+                    vec![deep.suffix[3].clone(), elem],
+                )
+            }
+            Tree::Deep(deep) => {
+                let mut suffix = deep.suffix.clone();
+                suffix.push(elem);
+                Tree::deep(deep.prefix.clone(), deep.middle.clone(), suffix)
+            }
+        }
+    }
+
+    fn view_front(&self) -> Option<(Elem<T, P>, Self)> {
+        match self {
+            Tree::Empty => None,
+            Tree::Single(elem) => Some((elem.clone(), Tree::Empty)),
+            Tree::Deep(deep) => Some((
+                deep.prefix[0].clone(),
+                Tree::deep_l(&deep.prefix[1..], &deep.middle, &deep.suffix),
+            )),
+        }
+    }
+
+    fn view_back(&self) -> Option<(Elem<T, P>, Self)> {
+        match self {
+            Tree::Empty => None,
+            Tree::Single(elem) => Some((elem.clone(), Tree::Empty)),
+            Tree::Deep(deep) => {
+                let (last, suffix) = deep.suffix.split_last().unwrap();
+                Some((
+                    last.clone(),
+                    Tree::deep_r(&deep.prefix, &deep.middle, suffix),
+                ))
+            }
+        }
+    }
+
+    /// A `Deep` tree whose prefix may have run out, refilled from the
+    /// middle if it did.
+    fn deep_l(prefix: &[Elem<T, P>], middle: &Self, suffix: &[Elem<T, P>]) -> Self {
+        if !prefix.is_empty() {
+            return Tree::deep(prefix.to_vec(), middle.clone(), suffix.to_vec());
+        }
+        match middle.view_front() {
+            Some((node, middle)) => Tree::deep(node.children().to_vec(), middle, suffix.to_vec()),
+            None => Tree::from_digit(suffix),
+        }
+    }
+
+    /// `deep_l` for a suffix that may have run out.
+    fn deep_r(prefix: &[Elem<T, P>], middle: &Self, suffix: &[Elem<T, P>]) -> Self {
+        if !suffix.is_empty() {
+            return Tree::deep(prefix.to_vec(), middle.clone(), suffix.to_vec());
+        }
+        match middle.view_back() {
+            Some((node, middle)) => Tree::deep(prefix.to_vec(), middle, node.children().to_vec()),
+            None => Tree::from_digit(prefix),
+        }
+    }
+
+    /// `self`, then `elems`, then `other`.
+    fn app3(&self, elems: &[Elem<T, P>], other: &Self) -> Self {
+        match (self, other) {
+            (Tree::Empty, _) => elems
+                .iter()
+                .rev()
+                .fold(other.clone(), |tree, elem| tree.push_front(elem.clone())),
+            (_, Tree::Empty) => elems
+                .iter()
+                .fold(self.clone(), |tree, elem| tree.push_back(elem.clone())),
+            (Tree::Single(first), _) => Tree::Empty.app3(elems, other).push_front(first.clone()),
+            (_, Tree::Single(last)) => self.app3(elems, &Tree::Empty).push_back(last.clone()),
+            (Tree::Deep(left), Tree::Deep(right)) => {
+                // The inner digits and `elems` go down a level as nodes
+                let mut between = left.suffix.clone();
+                between.extend(elems.iter().cloned());
+                between.extend(right.prefix.iter().cloned());
+                Tree::deep(
+                    left.prefix.clone(),
+                    left.middle.app3(&nodes(&between), &right.middle),
+                    right.suffix.clone(),
+                )
+            }
+        }
+    }
+
+    /// Everything before the element holding position `i`, that element,
+    /// and everything after it. `i` must be in bounds.
+    fn split(&self, i: usize) -> (Self, Elem<T, P>, Self) {
+        match self {
+            Tree::Empty => unreachable!("split an empty tree"),
+            Tree::Single(elem) => (Tree::Empty, elem.clone(), Tree::Empty),
+            Tree::Deep(deep) => {
+                let prefix_size = digit_size(&deep.prefix);
+                let middle_size = deep.middle.size();
+                if i < prefix_size {
+                    let (j, _) = locate(&deep.prefix, i);
+                    (
+                        Tree::from_digit(&deep.prefix[..j]),
+                        deep.prefix[j].clone(),
+                        Tree::deep_l(&deep.prefix[j + 1..], &deep.middle, &deep.suffix),
+                    )
+                } else if i < prefix_size + middle_size {
+                    let i = i - prefix_size;
+                    let (before, node, after) = deep.middle.split(i);
+                    let children = node.children();
+                    let (j, _) = locate(children, i - before.size());
+                    (
+                        Tree::deep_r(&deep.prefix, &before, &children[..j]),
+                        children[j].clone(),
+                        Tree::deep_l(&children[j + 1..], &after, &deep.suffix),
+                    )
+                } else {
+                    let (j, _) = locate(&deep.suffix, i - prefix_size - middle_size);
+                    (
+                        Tree::deep_r(&deep.prefix, &deep.middle, &deep.suffix[..j]),
+                        deep.suffix[j].clone(),
+                        Tree::from_digit(&deep.suffix[j + 1..]),
+                    )
+                }
+            }
+        }
+    }
+
+    fn lookup(&self, i: usize) -> &T {
+        match self {
+            Tree::Empty => unreachable!("lookup in an empty tree"),
+            Tree::Single(elem) => elem.lookup(i),
+            Tree::Deep(deep) => {
+                let prefix_size = digit_size(&deep.prefix);
+                let middle_size = deep.middle.size();
+                if i < prefix_size {
+                    let (j, i) = locate(&deep.prefix, i);
+                    deep.prefix[j].lookup(i)
+                } else if i < prefix_size + middle_size {
+                    deep.middle.lookup(i - prefix_size)
+                } else {
+                    let (j, i) = locate(&deep.suffix, i - prefix_size - middle_size);
+                    deep.suffix[j].lookup(i)
+                }
+            }
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Clone for Elem<T, P> {
+    fn clone(&self) -> Self {
+        match self {
+            Elem::Leaf(elem) => Elem::Leaf(elem.clone()),
+            Elem::Node(node) => Elem::Node(node.clone()),
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Clone for Tree<T, P> {
+    fn clone(&self) -> Self {
+        match self {
+            Tree::Empty => Tree::Empty,
+            Tree::Single(elem) => Tree::Single(elem.clone()),
+            Tree::Deep(deep) => Tree::Deep(deep.clone()),
+        }
+    }
+}
+
+impl<T> Deque<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, P: RefCountedFamily> Deque<T, P> {
+    pub fn len(&self) -> usize {
+        self.tree.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, at: usize) -> Option<&T> {
+        (at < self.len()).then(|| self.tree.lookup(at))
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|last| self.get(last))
+    }
+
+    pub fn push_front(&self, elem: T) -> Self {
+        Deque {
+            tree: self.tree.push_front(Elem::Leaf(P::new(elem))),
+        }
+    }
+
+    pub fn push_back(&self, elem: T) -> Self {
+        Deque {
+            tree: self.tree.push_back(Elem::Leaf(P::new(elem))),
+        }
+    }
+
+    /// The front element and the deque without it.
+    pub fn pop_front(&self) -> Option<(&T, Self)> {
+        let elem = self.front()?;
+        let (_, tree) = self.tree.view_front()?;
+        Some((elem, Deque { tree }))
+    }
+
+    /// The back element and the deque without it.
+    pub fn pop_back(&self) -> Option<(&T, Self)> {
+        let elem = self.back()?;
+        let (_, tree) = self.tree.view_back()?;
+        Some((elem, Deque { tree }))
+    }
+
+    /// `self` followed by `other`.
+    pub fn concat(&self, other: &Self) -> Self {
+        Deque {
+            tree: self.tree.app3(&[], &other.tree),
+        }
+    }
+
+    /// The first `at` elements and the rest.
+    ///
+    /// # Panics
+    ///
+    /// If `at > len`.
+    pub fn split_at(&self, at: usize) -> (Self, Self) {
+        assert!(
+            at <= self.len(),
+            "cannot split at a nonexistent index (is {at}, len is {})",
+            self.len()
+        );
+        if at == self.len() {
+            return (self.clone(), Self::default());
+        }
+        let (before, elem, after) = self.tree.split(at);
+        (
+            Deque { tree: before },
+            Deque {
+                tree: after.push_front(elem),
+            },
+        )
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            stack: vec![Pending::Tree(&self.tree)],
+            len: self.len(),
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Clone for Deque<T, P> {
+    fn clone(&self) -> Self {
+        Deque {
+            tree: self.tree.clone(),
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Default for Deque<T, P> {
+    fn default() -> Self {
+        Deque { tree: Tree::Empty }
+    }
+}
+
+impl<T, P: RefCountedFamily> FromIterator<T> for Deque<T, P> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let tree = iter.into_iter().fold(Tree::Empty, |tree, elem| {
+            tree.push_back(Elem::Leaf(P::new(elem)))
+        });
+        Deque { tree }
+    }
+}
+
+impl<T: Debug, P: RefCountedFamily> Debug for Deque<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a, T, P: RefCountedFamily = RcFamily> {
+    /// What's left to visit, the next part on top
+    stack: Vec<Pending<'a, T, P>>,
+    len: usize,
+}
+
+enum Pending<'a, T, P: RefCountedFamily> {
+    Tree(&'a Tree<T, P>),
+    Elem(&'a Elem<T, P>),
+}
+
+impl<'a, T, P: RefCountedFamily> Iterator for Iter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.pop()? {
+                Pending::Elem(Elem::Leaf(elem)) => {
+                    self.len -= 1;
+                    return Some(elem);
+                }
+                Pending::Elem(Elem::Node(node)) => {
+                    self.stack
+                        .extend(node.children.iter().rev().map(Pending::Elem));
+                }
+                Pending::Tree(Tree::Empty) => {}
+                Pending::Tree(Tree::Single(elem)) => self.stack.push(Pending::Elem(elem)),
+                Pending::Tree(Tree::Deep(deep)) => {
+                    self.stack
+                        .extend(deep.suffix.iter().rev().map(Pending::Elem));
+                    self.stack.push(Pending::Tree(&deep.middle));
+                    self.stack
+                        .extend(deep.prefix.iter().rev().map(Pending::Elem));
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T, P: RefCountedFamily> ExactSizeIterator for Iter<'a, T, P> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Deque;
+    use crate::testing::Rng;
+
+    #[test]
+    fn basics() {
+        let deque = Deque::new();
+        assert!(deque.pop_front().is_none());
+        assert!(deque.pop_back().is_none());
+        assert_eq!(deque.front(), None);
+
+        let deque = deque.push_back(2).push_back(3).push_front(1);
+        assert_eq!(deque.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&3));
+
+        let (elem, rest) = deque.pop_back().unwrap();
+        assert_eq!(elem, &3);
+        let (elem, rest) = rest.pop_front().unwrap();
+        assert_eq!(elem, &1);
+        assert_eq!(rest.iter().collect::<Vec<_>>(), [&2]);
+
+        // Old versions are untouched
+        assert_eq!(deque.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+    }
+
+    #[test]
+    fn concat_split_get() {
+        let n = 1000;
+        let deque: Deque<u32> = (0..n).collect();
+        for i in 0..n {
+            assert_eq!(deque.get(i as usize), Some(&i));
+        }
+        assert_eq!(deque.get(n as usize), None);
+
+        for at in [0, 1, 2, 5, 37, 500, 998, 999, 1000] {
+            let (left, right) = deque.split_at(at);
+            assert_eq!(left.len(), at);
+            assert!(left.iter().copied().eq(0..at as u32));
+            assert!(right.iter().copied().eq(at as u32..n));
+            assert!(left.concat(&right).iter().eq(deque.iter()));
+        }
+
+        // Concatenating mismatched sizes in both directions
+        let small: Deque<u32> = (0..3).collect();
+        let joined = small.concat(&deque).concat(&small);
+        assert_eq!(joined.len(), 1006);
+        assert_eq!(joined.get(3), Some(&0));
+        assert_eq!(joined.get(1004), Some(&1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_out_of_bounds() {
+        let deque: Deque<u32> = (0..3).collect();
+        deque.split_at(4);
+    }
+
+    /// A deque made by concatenating and splitting random smaller ones, and
+    /// the `Vec` it should hold.
+    fn random_tree(rng: &mut Rng, depth: u32, next: &mut u32) -> (Deque<u32>, Vec<u32>) {
+        if depth == 0 || rng.below(4) == 0 {
+            let mut deque = Deque::new();
+            let mut model = Vec::new();
+            for _ in 0..rng.below(40) {
+                *next += 1;
+                if rng.below(2) == 0 {
+                    deque = deque.push_front(*next);
+                    model.insert(0, *next);
+                } else {
+                    deque = deque.push_back(*next);
+                    model.push(*next);
+                }
+            }
+            return (deque, model);
+        }
+        let (left, mut left_model) = random_tree(rng, depth - 1, next);
+        if rng.below(3) == 0 {
+            let at = rng.below(left_model.len() as u64 + 1) as usize;
+            let (front, back) = left.split_at(at);
+            let back_model = left_model.split_off(at);
+            assert!(back.iter().eq(&back_model));
+            return if rng.below(2) == 0 {
+                (front, left_model)
+            } else {
+                (back, back_model)
+            };
+        }
+        let (right, right_model) = random_tree(rng, depth - 1, next);
+        left_model.extend(right_model);
+        (left.concat(&right), left_model)
+    }
+
+    #[test]
+    fn random_concat_split() {
+        for seed in 0..50 {
+            let mut rng = Rng::new(seed);
+            let (deque, model) = random_tree(&mut rng, 8, &mut 0);
+            assert_eq!(deque.len(), model.len());
+            assert!(deque.iter().eq(&model));
+            for (i, elem) in model.iter().enumerate() {
+                assert_eq!(deque.get(i), Some(elem));
+            }
+            assert_eq!(deque.get(model.len()), None);
+            assert_eq!(deque.front(), model.first());
+            assert_eq!(deque.back(), model.last());
+
+            // The ends still work after all the joining and splitting
+            let mut rest = deque;
+            let mut model = &model[..];
+            while let Some((elem, next)) = rest.pop_back() {
+                assert_eq!(Some(elem), model.last());
+                model = &model[..model.len() - 1];
+                rest = match next.pop_front() {
+                    Some((elem, next)) => {
+                        assert_eq!(Some(elem), model.first());
+                        model = &model[1..];
+                        next
+                    }
+                    None => next,
+                };
+            }
+            assert!(model.is_empty());
+        }
+    }
+}
@@ -0,0 +1,359 @@
+use crate::gat::{RcFamily, RefCountedFamily};
+use crate::persistent_stack::{self, List};
+
+/// A persistent FIFO queue: `push_back` and `pop_front` return a new
+/// version and leave `self` as it was.
+///
+/// This is Okasaki's real-time (Hood-Melville) queue. Elements are popped
+/// from `front` and pushed onto `rear`, and once `rear` gets longer than the
+/// front the rear is reversed onto the end of the front. Instead of doing
+/// that all at once, a few steps of the reversal are done by every
+/// operation, so each one is O(1) in the worst case, however old the version
+/// it's applied to. The reversal copies elements into new nodes, hence
+/// `T: Clone`.
+pub struct Queue<T, P: RefCountedFamily = RcFamily> {
+    front: List<T, P>,
+    /// Including everything the rotation will add to the front
+    front_len: usize,
+    rotation: Rotation<T, P>,
+    /// The rear being rotated into the front, only needed to iterate
+    rotating: List<T, P>,
+    rotating_len: usize,
+    /// The newest element first
+    rear: List<T, P>,
+    rear_len: usize,
+}
+
+/// The steps of turning `front ++ reverse(rear)` into a new front.
+enum Rotation<T, P: RefCountedFamily> {
+    Idle,
+    /// Reversing `front` onto `front_rev` and `rear` onto `rear_rev` in
+    /// lockstep. `ok` counts reversed front elements that haven't been
+    /// popped since.
+    Reversing {
+        ok: usize,
+        front: List<T, P>,
+        front_rev: List<T, P>,
+        rear: List<T, P>,
+        rear_rev: List<T, P>,
+    },
+    /// Moving the `ok` still wanted elements of `front_rev` onto `rear_rev`
+    Appending {
+        ok: usize,
+        front_rev: List<T, P>,
+        rear_rev: List<T, P>,
+    },
+    Done(List<T, P>),
+}
+
+impl<T: Clone, P: RefCountedFamily> Rotation<T, P> {
+    fn exec(&self) -> Self {
+        match self {
+            Rotation::Reversing {
+                ok,
+                front,
+                front_rev,
+                rear,
+                rear_rev,
+            } => match (front.head(), rear.head()) {
+                (Some(x), Some(y)) => Rotation::Reversing {
+                    ok: ok + 1,
+                    front: front.tail(),
+                    front_rev: front_rev.prepend(x.clone()),
+                    rear: rear.tail(),
+                    rear_rev: rear_rev.prepend(y.clone()),
+                },
+                // The rear starts out one longer than the front
+                (None, Some(y)) => Rotation::Appending {
+                    ok: *ok,
+                    front_rev: front_rev.clone(),
+                    rear_rev: rear_rev.prepend(y.clone()),
+                },
+                _ => unreachable!("rotation started with a rear no longer than the front"),
+            },
+            Rotation::Appending {
+                ok: 0, rear_rev, ..
+            } => Rotation::Done(rear_rev.clone()),
+            Rotation::Appending {
+                ok,
+                front_rev,
+                rear_rev,
+            } => {
+                let x = front_rev.head().unwrap();
+                Rotation::Appending {
+                    ok: ok - 1,
+                    front_rev: front_rev.tail(),
+                    rear_rev: rear_rev.prepend(x.clone()),
+                }
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// One of the front elements being rotated was just popped.
+    fn invalidate(&self) -> Self {
+        match self {
+            Rotation::Reversing {
+                ok,
+                front,
+                front_rev,
+                rear,
+                rear_rev,
+            } => Rotation::Reversing {
+                ok: ok - 1,
+                front: front.clone(),
+                front_rev: front_rev.clone(),
+                rear: rear.clone(),
+                rear_rev: rear_rev.clone(),
+            },
+            // Every front element left is popped, drop its copy too
+            Rotation::Appending {
+                ok: 0, rear_rev, ..
+            } => Rotation::Done(rear_rev.tail()),
+            Rotation::Appending {
+                ok,
+                front_rev,
+                rear_rev,
+            } => Rotation::Appending {
+                ok: ok - 1,
+                front_rev: front_rev.clone(),
+                rear_rev: rear_rev.clone(),
+            },
+            other => other.clone(),
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Clone for Rotation<T, P> {
+    fn clone(&self) -> Self {
+        match self {
+            Rotation::Idle => Rotation::Idle,
+            Rotation::Reversing {
+                ok,
+                front,
+                front_rev,
+                rear,
+                rear_rev,
+            } => Rotation::Reversing {
+                ok: *ok,
+                front: front.clone(),
+                front_rev: front_rev.clone(),
+                rear: rear.clone(),
+                rear_rev: rear_rev.clone(),
+            },
+            Rotation::Appending {
+                ok,
+                front_rev,
+                rear_rev,
+            } => Rotation::Appending {
+                ok: *ok,
+                front_rev: front_rev.clone(),
+                rear_rev: rear_rev.clone(),
+            },
+            Rotation::Done(list) => Rotation::Done(list.clone()),
+        }
+    }
+}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, P: RefCountedFamily> Queue<T, P> {
+    pub fn len(&self) -> usize {
+        self.front_len + self.rear_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.front.head()
+    }
+
+    /// Front to back. Setting up the iterator walks the rear once, so it
+    /// costs O(n) up front.
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        // The rotating and the current rear both come out newest first
+        let mut back: Vec<&T> = Vec::with_capacity(self.rotating_len + self.rear_len);
+        back.extend(self.rear.iter());
+        back.extend(self.rotating.iter());
+        Iter {
+            front: self.front.iter(),
+            back,
+        }
+    }
+}
+
+impl<T: Clone, P: RefCountedFamily> Queue<T, P> {
+    pub fn push_back(&self, elem: T) -> Self {
+        Queue {
+            rear: self.rear.prepend(elem),
+            rear_len: self.rear_len + 1,
+            ..self.clone()
+        }
+        .check()
+    }
+
+    /// The front element and the queue without it.
+    pub fn pop_front(&self) -> Option<(&T, Self)> {
+        let elem = self.front.head()?;
+        let rest = Queue {
+            front: self.front.tail(),
+            front_len: self.front_len - 1,
+            rotation: self.rotation.invalidate(),
+            ..self.clone()
+        }
+        .check();
+        Some((elem, rest))
+    }
+
+    /// Start a rotation once the rear outgrows the front, and move any
+    /// rotation along.
+    fn check(self) -> Self {
+        if self.rear_len <= self.front_len {
+            return self.exec2();
+        }
+        Queue {
+            rotation: Rotation::Reversing {
+                ok: 0,
+                front: self.front.clone(),
+                front_rev: List::default(),
+                rear: self.rear.clone(),
+                rear_rev: List::default(),
+            },
+            front_len: self.front_len + self.rear_len,
+            rotating: self.rear,
+            rotating_len: self.rear_len,
+            rear: List::default(),
+            rear_len: 0,
+            front: self.front,
+        }
+        .exec2()
+    }
+
+    fn exec2(mut self) -> Self {
+        match self.rotation.exec().exec() {
+            Rotation::Done(front) => {
+                self.front = front;
+                self.rotation = Rotation::Idle;
+                self.rotating = List::default();
+                self.rotating_len = 0;
+            }
+            rotation => self.rotation = rotation,
+        }
+        self
+    }
+}
+
+impl<T, P: RefCountedFamily> Clone for Queue<T, P> {
+    fn clone(&self) -> Self {
+        Queue {
+            front: self.front.clone(),
+            front_len: self.front_len,
+            rotation: self.rotation.clone(),
+            rotating: self.rotating.clone(),
+            rotating_len: self.rotating_len,
+            rear: self.rear.clone(),
+            rear_len: self.rear_len,
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Default for Queue<T, P> {
+    fn default() -> Self {
+        Queue {
+            front: List::default(),
+            front_len: 0,
+            rotation: Rotation::Idle,
+            rotating: List::default(),
+            rotating_len: 0,
+            rear: List::default(),
+            rear_len: 0,
+        }
+    }
+}
+
+pub struct Iter<'a, T, P: RefCountedFamily = RcFamily> {
+    front: persistent_stack::Iter<'a, T, P>,
+    /// Everything behind the front, back to front
+    back: Vec<&'a T>,
+}
+
+impl<'a, T, P: RefCountedFamily> Iterator for Iter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.next().or_else(|| self.back.pop())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Queue;
+    use crate::testing::check_versions;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn basics() {
+        let queue = Queue::new();
+        assert!(queue.pop_front().is_none());
+        assert_eq!(queue.peek(), None);
+
+        let queue = queue.push_back(1).push_back(2).push_back(3);
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+
+        let (elem, rest) = queue.pop_front().unwrap();
+        assert_eq!(elem, &1);
+        let rest = rest.push_back(4);
+        assert_eq!(rest.iter().collect::<Vec<_>>(), [&2, &3, &4]);
+
+        // The old version is untouched
+        assert_eq!(queue.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn versions() {
+        check_versions(
+            500,
+            (Queue::new(), VecDeque::new()),
+            |rng, i, queue, mut model| {
+                if rng.below(3) != 0 {
+                    model.push_back(i);
+                    return (queue.push_back(i), model);
+                }
+                match queue.pop_front() {
+                    Some((elem, rest)) => {
+                        assert_eq!(Some(*elem), model.pop_front());
+                        (rest, model)
+                    }
+                    None => (queue, model),
+                }
+            },
+            |queue, model| {
+                assert_eq!(queue.len(), model.len());
+                assert_eq!(queue.peek(), model.front());
+                assert!(queue.iter().eq(model));
+            },
+        );
+    }
+
+    #[test]
+    fn long() {
+        let mut queue = Queue::new();
+        for i in 0..100_000 {
+            queue = queue.push_back(i);
+        }
+        for i in 0..100_000 {
+            let (elem, rest) = queue.pop_front().unwrap();
+            assert_eq!(*elem, i);
+            queue = rest;
+        }
+        assert!(queue.is_empty());
+    }
+}
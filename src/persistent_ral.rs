@@ -0,0 +1,427 @@
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+
+use crate::gat::{ArcFamily, RcFamily, RefCountedFamily};
+
+type Ptr<P, X> = <P as RefCountedFamily>::Pointer<X>;
+type Link<T, P> = Option<Ptr<P, Spine<T, P>>>;
+type Subtree<T, P> = Ptr<P, Tree<T, P>>;
+
+/// A persistent random-access list, Okasaki's skew-binary one.
+///
+/// Like `persistent_stack::List` it has O(1) `prepend`, `head` and `tail`,
+/// but reaching the element at an index is O(log n) instead of O(n), and so
+/// is `set`. The elements live in complete binary trees of `2^k - 1`
+/// elements each, kept in a list from small to large. Only the first two
+/// trees can be the same size, and prepending to two same sized trees joins
+/// them under a new root. So there are O(log n) trees and each one is
+/// O(log n) deep.
+pub struct List<T, P: RefCountedFamily = RcFamily> {
+    head: Link<T, P>,
+    len: usize,
+}
+
+struct Spine<T, P: RefCountedFamily> {
+    /// How many elements `tree` holds
+    size: usize,
+    tree: Subtree<T, P>,
+    next: Link<T, P>,
+}
+
+/// A complete binary tree, with its elements in preorder.
+struct Tree<T, P: RefCountedFamily> {
+    elem: T,
+    children: Option<(Subtree<T, P>, Subtree<T, P>)>,
+}
+
+impl<T, P: RefCountedFamily> Tree<T, P> {
+    fn leaf(elem: T) -> Self {
+        Tree {
+            elem,
+            children: None,
+        }
+    }
+
+    fn get(&self, size: usize, at: usize) -> &T {
+        let (mut tree, mut size, mut at) = (self, size, at);
+        while at > 0 {
+            let (left, right) = tree.children.as_ref().unwrap();
+            size /= 2;
+            if at <= size {
+                tree = left;
+                at -= 1;
+            } else {
+                tree = right;
+                at -= 1 + size;
+            }
+        }
+        &tree.elem
+    }
+
+    /// A copy of the path down to `at`, sharing everything else.
+    fn set(&self, size: usize, at: usize, elem: T) -> Self
+    where
+        T: Clone,
+    {
+        if at == 0 {
+            return Tree {
+                elem,
+                children: self.children.clone(),
+            };
+        }
+        let (left, right) = self.children.as_ref().unwrap();
+        let size = size / 2;
+        let children = if at <= size {
+            (P::new(left.set(size, at - 1, elem)), right.clone())
+        } else {
+            (left.clone(), P::new(right.set(size, at - 1 - size, elem)))
+        };
+        Tree {
+            elem: self.elem.clone(),
+            children: Some(children),
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Spine<T, P> {
+    /// A copy of the spine down to the tree holding `at`, sharing the rest.
+    fn set(&self, at: usize, elem: T) -> Self
+    where
+        T: Clone,
+    {
+        if at < self.size {
+            Spine {
+                size: self.size,
+                tree: P::new(self.tree.set(self.size, at, elem)),
+                next: self.next.clone(),
+            }
+        } else {
+            let next = self.next.as_ref().unwrap();
+            Spine {
+                size: self.size,
+                tree: self.tree.clone(),
+                next: Some(P::new(next.set(at - self.size, elem))),
+            }
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Drop for List<T, P> {
+    fn drop(&mut self) {
+        // The trees are only O(log n) deep, so they can drop recursively.
+        // `into_inner` so that threads letting go of a shared spine at once
+        // don't all stop, as in `persistent_stack`
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            if let Some(mut node) = P::into_inner(node) {
+                head = node.next.take();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> List<T> {
+        List { head: None, len: 0 }
+    }
+}
+
+impl<T, P: RefCountedFamily> List<T, P> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|spine| &spine.tree.elem)
+    }
+
+    pub fn tail(&self) -> List<T, P> {
+        let Some(first) = &self.head else {
+            return List::default();
+        };
+        let head = match &first.tree.children {
+            None => first.next.clone(),
+            // Losing the root leaves two trees of half the size
+            Some((left, right)) => {
+                let size = first.size / 2;
+                Some(P::new(Spine {
+                    size,
+                    tree: left.clone(),
+                    next: Some(P::new(Spine {
+                        size,
+                        tree: right.clone(),
+                        next: first.next.clone(),
+                    })),
+                }))
+            }
+        };
+        List {
+            head,
+            len: self.len - 1,
+        }
+    }
+
+    pub fn prepend(&self, elem: T) -> List<T, P> {
+        let head = match &self.head {
+            Some(first) => match &first.next {
+                Some(second) if first.size == second.size => Spine {
+                    size: 1 + 2 * first.size,
+                    tree: P::new(Tree {
+                        elem,
+                        children: Some((first.tree.clone(), second.tree.clone())),
+                    }),
+                    next: second.next.clone(),
+                },
+                _ => Spine {
+                    size: 1,
+                    tree: P::new(Tree::leaf(elem)),
+                    next: self.head.clone(),
+                },
+            },
+            None => Spine {
+                size: 1,
+                tree: P::new(Tree::leaf(elem)),
+                next: None,
+            },
+        };
+        List {
+            head: Some(P::new(head)),
+            len: self.len + 1,
+        }
+    }
+
+    pub fn get(&self, at: usize) -> Option<&T> {
+        let mut at = at;
+        let mut spine = self.head.as_deref();
+        while let Some(node) = spine {
+            if at < node.size {
+                return Some(node.tree.get(node.size, at));
+            }
+            at -= node.size;
+            spine = node.next.as_deref();
+        }
+        None
+    }
+
+    /// A new version with the element at `at` replaced. Only the O(log n)
+    /// nodes on the way to it are copied, which clones their elements.
+    ///
+    /// # Panics
+    ///
+    /// If `at >= len`.
+    pub fn set(&self, at: usize, elem: T) -> List<T, P>
+    where
+        T: Clone,
+    {
+        assert!(
+            at < self.len,
+            "index out of bounds: the len is {} but the index is {at}",
+            self.len
+        );
+        List {
+            head: Some(P::new(self.head.as_ref().unwrap().set(at, elem))),
+            len: self.len,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            spine: self.head.as_deref(),
+            trees: Vec::new(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Clone for List<T, P> {
+    fn clone(&self) -> Self {
+        List {
+            head: self.head.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Default for List<T, P> {
+    fn default() -> Self {
+        List { head: None, len: 0 }
+    }
+}
+
+impl<T, P: RefCountedFamily> FromIterator<T> for List<T, P> {
+    /// The first element of the iterator ends up at the head.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        elems
+            .into_iter()
+            .rev()
+            .fold(List::default(), |list, elem| list.prepend(elem))
+    }
+}
+
+impl<T: Debug, P: RefCountedFamily> Debug for List<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T, P: RefCountedFamily> IntoIterator for &'a List<T, P> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, T, P: RefCountedFamily = RcFamily> {
+    /// The trees not started on yet
+    spine: Option<&'a Spine<T, P>>,
+    /// What's left of the current tree, the next subtree on top
+    trees: Vec<&'a Tree<T, P>>,
+    len: usize,
+}
+
+impl<'a, T, P: RefCountedFamily> Iterator for Iter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(tree) = self.trees.pop() {
+                if let Some((left, right)) = &tree.children {
+                    self.trees.push(right);
+                    self.trees.push(left);
+                }
+                self.len -= 1;
+                return Some(&tree.elem);
+            }
+            let spine = self.spine?;
+            self.spine = spine.next.as_deref();
+            self.trees.push(&spine.tree);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T, P: RefCountedFamily> ExactSizeIterator for Iter<'a, T, P> {}
+
+#[allow(dead_code)]
+fn assert_properties() {
+    fn is_send<T: Send>() {}
+    fn is_sync<T: Sync>() {}
+
+    is_send::<List<i32, ArcFamily>>();
+    is_sync::<List<i32, ArcFamily>>();
+
+    is_send::<Iter<i32, ArcFamily>>();
+    is_sync::<Iter<i32, ArcFamily>>();
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+
+    #[test]
+    fn basics() {
+        let list = List::new();
+        assert_eq!(list.head(), None);
+        assert_eq!(list.get(0), None);
+
+        let list = list.prepend(1).prepend(2).prepend(3);
+        assert_eq!(list.head(), Some(&3));
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &2, &1]);
+
+        let list = list.tail();
+        assert_eq!(list.head(), Some(&2));
+
+        let list = list.tail();
+        assert_eq!(list.head(), Some(&1));
+
+        let list = list.tail();
+        assert_eq!(list.head(), None);
+
+        // Make sure empty tail works
+        let list = list.tail();
+        assert_eq!(list.head(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn get_set() {
+        let n = 1000;
+        let list: List<u32> = (0..n).collect();
+        for i in 0..n {
+            assert_eq!(list.get(i as usize), Some(&i));
+        }
+        assert_eq!(list.get(n as usize), None);
+
+        let changed = list.set(0, 100).set(500, 200).set(999, 300);
+        assert_eq!(changed.get(0), Some(&100));
+        assert_eq!(changed.get(500), Some(&200));
+        assert_eq!(changed.get(999), Some(&300));
+        assert_eq!(changed.get(501), Some(&501));
+        // The old version is untouched
+        assert!(list.iter().copied().eq(0..n));
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_out_of_bounds() {
+        let list: List<u32> = (0..3).collect();
+        list.set(3, 0);
+    }
+
+    #[test]
+    fn tree_boundaries() {
+        // Sizes on both sides of a full tree, with `tail` splitting the
+        // first tree a few times over
+        for size in [1, 2, 3, 6, 7, 8, 14, 15, 16, 30, 31, 32, 62, 63, 64] {
+            let full: List<u32> = (0..size).collect();
+            let mut list = full.clone();
+            for dropped in 0..size {
+                let model: Vec<u32> = (dropped..size).collect();
+                for (at, elem) in model.iter().enumerate() {
+                    assert_eq!(list.get(at), Some(elem));
+                }
+                assert_eq!(list.get(model.len()), None);
+
+                for at in 0..model.len() {
+                    let changed = list.set(at, 1000);
+                    for (i, elem) in model.iter().enumerate() {
+                        let expected = if i == at { &1000 } else { elem };
+                        assert_eq!(changed.get(i), Some(expected));
+                    }
+                    assert!(changed.tail().iter().eq(changed.iter().skip(1)));
+                }
+                assert!(list.iter().eq(&model));
+                list = list.tail();
+            }
+            assert!(list.is_empty());
+            assert!(full.iter().copied().eq(0..size));
+        }
+    }
+
+    #[test]
+    fn long() {
+        let mut list = List::new();
+        for i in 0..200_000 {
+            list = list.prepend(i);
+        }
+        assert_eq!(list.get(0), Some(&199_999));
+        assert_eq!(list.get(199_999), Some(&0));
+        for i in (0..200_000).rev() {
+            assert_eq!(list.head(), Some(&i));
+            list = list.tail();
+        }
+        assert!(list.is_empty());
+    }
+}
@@ -1,21 +1,42 @@
-use std::rc::Rc;
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
 
-type Link<T> = Option<Rc<Node<T>>>;
+use crate::gat::{ArcFamily, RcFamily, RefCountedFamily};
 
-struct Node<T> {
+type Link<T, P> = Option<<P as RefCountedFamily>::Pointer<Node<T, P>>>;
+
+struct Node<T, P: RefCountedFamily> {
     elem: T,
-    next: Link<T>,
+    next: Link<T, P>,
+}
+
+/// A persistent stack whose nodes are shared through `P`'s pointers. With
+/// the default `RcFamily` it stays on one thread, with `ArcFamily` the list
+/// and everything derived from it can be sent and shared across threads.
+pub struct List<T, P: RefCountedFamily = RcFamily> {
+    head: Link<T, P>,
+    len: usize,
 }
 
-pub struct List<T> {
-    head: Link<T>,
+impl<T: Clone, P: RefCountedFamily> Clone for Node<T, P> {
+    fn clone(&self) -> Self {
+        Node {
+            elem: self.elem.clone(),
+            next: self.next.clone(),
+        }
+    }
 }
 
-impl<T> Drop for List<T> {
+impl<T, P: RefCountedFamily> Drop for List<T, P> {
     fn drop(&mut self) {
+        // `into_inner` rather than `try_unwrap`, so that when several
+        // threads let go of a shared node at once, one of them still gets it
+        // and carries on down the list
         let mut head = self.head.take();
         while let Some(node) = head {
-            if let Ok(mut node) = Rc::try_unwrap(node) {
+            if let Some(mut node) = P::into_inner(node) {
                 head = node.next.take();
             } else {
                 break;
@@ -24,11 +45,11 @@ impl<T> Drop for List<T> {
     }
 }
 
-pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>,
+pub struct Iter<'a, T, P: RefCountedFamily = RcFamily> {
+    next: Option<&'a Node<T, P>>,
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, P: RefCountedFamily> Iterator for Iter<'a, T, P> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -41,10 +62,20 @@ impl<'a, T> Iterator for Iter<'a, T> {
 
 impl<T> List<T> {
     pub fn new() -> List<T> {
-        List { head: None }
+        List { head: None, len: 0 }
+    }
+}
+
+impl<T, P: RefCountedFamily> List<T, P> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<T, P> {
         Iter {
             next: self.head.as_deref(),
         }
@@ -54,25 +85,315 @@ impl<T> List<T> {
         self.head.as_ref().map(|node| &node.elem)
     }
 
-    pub fn tail(&self) -> List<T> {
+    pub fn tail(&self) -> List<T, P> {
         return List {
             head: self.head.as_ref().and_then(|node| node.next.clone()),
+            len: self.len.saturating_sub(1),
         };
     }
 
-    pub fn prepend(&self, elem: T) -> List<T> {
+    pub fn prepend(&self, elem: T) -> List<T, P> {
         List {
-            head: Some(Rc::new(Node {
+            head: Some(P::new(Node {
                 elem,
                 next: self.head.clone(),
             })),
+            len: self.len + 1,
+        }
+    }
+
+    /// Like `prepend`, but reuses `self` instead of cloning the head pointer.
+    pub fn push(mut self, elem: T) -> List<T, P> {
+        let next = self.head.take();
+        self.head = Some(P::new(Node { elem, next }));
+        self.len += 1;
+        self
+    }
+
+    /// All the elements in order, if no other list shares any of the nodes.
+    /// Otherwise nothing is cloned and the list comes back with the same
+    /// elements, though the ones before the first shared node end up in new
+    /// nodes.
+    pub fn try_into_vec(mut self) -> Result<Vec<T>, List<T, P>> {
+        let mut elems = Vec::with_capacity(self.len);
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            match P::try_unwrap(node) {
+                Ok(mut node) => {
+                    head = node.next.take();
+                    elems.push(node.elem);
+                }
+                Err(node) => {
+                    let shared = List {
+                        head: Some(node),
+                        len: self.len - elems.len(),
+                    };
+                    return Err(elems
+                        .into_iter()
+                        .rev()
+                        .fold(shared, |list, elem| list.prepend(elem)));
+                }
+            }
+        }
+        Ok(elems)
+    }
+
+    /// The head and the rest of the list. The head is moved out if no other
+    /// list holds its node, and only cloned if one does.
+    pub fn pop(mut self) -> Option<(T, List<T, P>)>
+    where
+        T: Clone,
+    {
+        let node = self.head.take()?;
+        let (elem, next) = match P::try_unwrap(node) {
+            Ok(mut node) => (node.elem, node.next.take()),
+            // The rest is ours through the cloned `next` before the node is
+            // let go, so even if that turns out to be its last reference
+            // only the node itself goes
+            Err(node) => (node.elem.clone(), node.next.clone()),
+        };
+        self.head = next;
+        self.len -= 1;
+        Some((elem, self))
+    }
+
+    /// The head to change in place, if no other list holds its node. Unlike
+    /// `head_mut` it never copies, so it works for any `T`.
+    pub fn try_head_mut(&mut self) -> Option<&mut T> {
+        self.head
+            .as_mut()
+            .and_then(P::get_mut)
+            .map(|node| &mut node.elem)
+    }
+
+    /// Changes the head in place if no other list holds its node. Otherwise
+    /// this list gets its own copy of the head node first, still sharing the
+    /// rest with everyone else.
+    pub fn head_mut(&mut self) -> Option<&mut T>
+    where
+        T: Clone,
+    {
+        self.head.as_mut().map(|node| &mut P::make_mut(node).elem)
+    }
+
+    /// Whether both are the very same list, not just equal ones.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => P::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// The list itself, then its tail, and so on down to the last element.
+    /// Every one of them shares its nodes with `self`.
+    pub fn tails(&self) -> Tails<T, P> {
+        Tails { next: self.clone() }
+    }
+}
+
+impl<T, P: RefCountedFamily> Clone for List<T, P> {
+    fn clone(&self) -> Self {
+        // Only the head pointer is copied, the nodes are shared
+        List {
+            head: self.head.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Default for List<T, P> {
+    fn default() -> Self {
+        List { head: None, len: 0 }
+    }
+}
+
+impl<T, P: RefCountedFamily> FromIterator<T> for List<T, P> {
+    /// The first element of the iterator ends up at the head.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        elems
+            .into_iter()
+            .rev()
+            .fold(List::default(), |list, elem| list.prepend(elem))
+    }
+}
+
+impl<T: Debug, P: RefCountedFamily> Debug for List<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, P: RefCountedFamily> PartialEq for List<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.len != other.len {
+            return false;
+        }
+        let (mut a, mut b) = (&self.head, &other.head);
+        while let (Some(x), Some(y)) = (a, b) {
+            // Both sides have as much left, so from a shared node on
+            // everything is shared
+            if P::ptr_eq(x, y) {
+                return true;
+            }
+            if x.elem != y.elem {
+                return false;
+            }
+            a = &x.next;
+            b = &y.next;
+        }
+        true
+    }
+}
+
+impl<T: Eq, P: RefCountedFamily> Eq for List<T, P> {}
+
+impl<T: PartialOrd, P: RefCountedFamily> PartialOrd for List<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord, P: RefCountedFamily> Ord for List<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash, P: RefCountedFamily> Hash for List<T, P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<'a, T, P: RefCountedFamily> IntoIterator for &'a List<T, P> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Moves the elements out of nodes no other list holds on to. At the first
+/// node that is still shared it either ends, leaving the rest to
+/// `into_shared`, or with `List::into_iter_cloned` clones from there on.
+pub struct IntoIter<T, P: RefCountedFamily = RcFamily> {
+    list: List<T, P>,
+    /// How to copy an element out of a shared node, if we may
+    clone: Option<fn(&T) -> T>,
+}
+
+impl<T, P: RefCountedFamily> IntoIterator for List<T, P> {
+    type Item = T;
+    type IntoIter = IntoIter<T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            list: self,
+            clone: None,
+        }
+    }
+}
+
+impl<T: Clone, P: RefCountedFamily> List<T, P> {
+    /// Like `into_iter`, but goes on past shared nodes, cloning their
+    /// elements.
+    pub fn into_iter_cloned(self) -> IntoIter<T, P> {
+        IntoIter {
+            list: self,
+            clone: Some(T::clone),
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> IntoIter<T, P> {
+    /// What hasn't been yielded, all of it still shared with another list
+    /// if the iterator stopped early.
+    pub fn into_shared(self) -> List<T, P> {
+        self.list
+    }
+}
+
+impl<T, P: RefCountedFamily> Iterator for IntoIter<T, P> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.list.head.take()?;
+        let elem = match P::try_unwrap(node) {
+            Ok(mut node) => {
+                self.list.head = node.next.take();
+                node.elem
+            }
+            Err(node) => {
+                let Some(clone) = self.clone else {
+                    self.list.head = Some(node);
+                    return None;
+                };
+                self.list.head = node.next.clone();
+                clone(&node.elem)
+            }
+        };
+        self.list.len -= 1;
+        Some(elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.list.len;
+        match self.clone {
+            Some(_) => (len, Some(len)),
+            None => (0, Some(len)),
         }
     }
 }
 
+pub struct Tails<T, P: RefCountedFamily = RcFamily> {
+    next: List<T, P>,
+}
+
+impl<T, P: RefCountedFamily> Iterator for Tails<T, P> {
+    type Item = List<T, P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next.is_empty() {
+            return None;
+        }
+        let tail = self.next.tail();
+        Some(std::mem::replace(&mut self.next, tail))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.next.len, Some(self.next.len))
+    }
+}
+
+impl<T, P: RefCountedFamily> ExactSizeIterator for Tails<T, P> {}
+
+#[allow(dead_code)]
+fn assert_properties() {
+    fn is_send<T: Send>() {}
+    fn is_sync<T: Sync>() {}
+
+    is_send::<List<i32, ArcFamily>>();
+    is_sync::<List<i32, ArcFamily>>();
+
+    is_send::<Iter<i32, ArcFamily>>();
+    is_sync::<Iter<i32, ArcFamily>>();
+}
+
 #[cfg(test)]
 mod test {
     use super::List;
+    use crate::gat::{ArcFamily, RcFamily, RefCountedFamily};
+    use std::cell::Cell;
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    use std::rc::Rc;
+    use std::sync::Barrier;
+    use std::thread;
 
     #[test]
     fn iter() {
@@ -105,4 +426,270 @@ mod test {
         let list = list.tail();
         assert_eq!(list.head(), None);
     }
+
+    #[test]
+    fn arc_across_threads() {
+        let list = List::<i32, ArcFamily>::default()
+            .prepend(1)
+            .prepend(2)
+            .prepend(3);
+
+        let sums: Vec<i32> = thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|i| {
+                    let list = list.prepend(i * 10);
+                    s.spawn(move || list.iter().sum())
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(sums, [6, 16, 26, 36]);
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &2, &1]);
+    }
+
+    #[test]
+    fn traits() {
+        let list: List<i32> = (1..=3).collect();
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+        assert_eq!(list.len(), 3);
+        assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+
+        let other = List::new().prepend(3).prepend(2).prepend(1);
+        assert_eq!(list, other);
+        assert_ne!(list, list.tail());
+        assert_ne!(list, other.tail().prepend(5));
+        assert!(list < other.tail().prepend(5));
+        assert!(list.tail() > list);
+
+        let hash = |list: &List<i32>| {
+            let mut hasher = DefaultHasher::new();
+            list.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&list), hash(&other));
+        assert_eq!(List::<i32>::default(), List::new());
+    }
+
+    #[test]
+    fn shared_suffix_eq() {
+        // An element that panics when compared, to show the shared part
+        // is never looked at
+        struct NoCmp;
+        impl PartialEq for NoCmp {
+            fn eq(&self, _: &Self) -> bool {
+                panic!("compared a shared element");
+            }
+        }
+
+        let shared: List<(u32, Option<NoCmp>)> = (0..1000).map(|i| (i, None)).collect();
+        let shared = shared.prepend((0, Some(NoCmp)));
+        let a = shared.prepend((1, None)).prepend((2, None));
+        let b = shared.prepend((1, None)).prepend((2, None));
+        assert!(a == b);
+        assert!(a != b.tail().prepend((3, None)));
+        assert!(a != shared);
+    }
+
+    #[test]
+    fn tails() {
+        let list: List<i32> = (1..=3).collect();
+        let tails: Vec<Vec<i32>> = list
+            .tails()
+            .map(|tail| tail.iter().cloned().collect())
+            .collect();
+        assert_eq!(tails, [vec![1, 2, 3], vec![2, 3], vec![3]]);
+        assert_eq!(list.tails().len(), 3);
+        assert_eq!(List::<i32>::new().tails().count(), 0);
+
+        // The suffixes are the list's own nodes, not copies
+        let last = list.tails().last().unwrap();
+        assert_eq!(last, list.tail().tail());
+    }
+
+    #[test]
+    fn into_iter() {
+        // Not `Clone`, so these can only ever be moved out
+        #[derive(Debug, PartialEq)]
+        struct Elem(u32);
+
+        let list: List<Elem> = (1..4).map(Elem).collect();
+        let elems: Vec<Elem> = list.into_iter().collect();
+        assert_eq!(elems, [Elem(1), Elem(2), Elem(3)]);
+
+        let shared: List<Elem> = (3..6).map(Elem).collect();
+        let list = shared.prepend(Elem(2)).prepend(Elem(1));
+        let mut iter = list.into_iter();
+        assert_eq!(iter.size_hint(), (0, Some(5)));
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), [Elem(1), Elem(2)]);
+        // Stops where `shared` still holds on
+        let rest = iter.into_shared();
+        assert!(rest.ptr_eq(&shared));
+        assert_eq!(rest.len(), 3);
+    }
+
+    #[test]
+    fn into_iter_cloned() {
+        // Counts its clones, so we can tell moved elements from cloned ones
+        struct Elem(u32, Rc<Cell<u32>>);
+        impl Clone for Elem {
+            fn clone(&self) -> Self {
+                self.1.set(self.1.get() + 1);
+                Elem(self.0, self.1.clone())
+            }
+        }
+
+        let clones = Rc::new(Cell::new(0));
+        let shared: List<Elem> = (3..6).map(|i| Elem(i, clones.clone())).collect();
+        let list = shared
+            .prepend(Elem(2, clones.clone()))
+            .prepend(Elem(1, clones.clone()));
+
+        let iter = list.into_iter_cloned();
+        assert_eq!(iter.size_hint(), (5, Some(5)));
+        let elems: Vec<u32> = iter.map(|elem| elem.0).collect();
+        assert_eq!(elems, [1, 2, 3, 4, 5]);
+        // Only the elements still held by `shared`
+        assert_eq!(clones.get(), 3);
+
+        let elems: Vec<u32> = shared.into_iter_cloned().map(|elem| elem.0).collect();
+        assert_eq!(elems, [3, 4, 5]);
+        assert_eq!(clones.get(), 3);
+    }
+
+    #[test]
+    fn try_into_vec() {
+        let list: List<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(list.try_into_vec().unwrap(), ["a", "b", "c"]);
+
+        let shared: List<String> = ["c", "d"].iter().map(|s| s.to_string()).collect();
+        let list = shared.prepend("b".to_string()).prepend("a".to_string());
+        let list = list.try_into_vec().unwrap_err();
+        assert_eq!(list.iter().collect::<Vec<_>>(), ["a", "b", "c", "d"]);
+        assert_eq!(list.len(), 4);
+
+        drop(shared);
+        assert_eq!(list.try_into_vec().unwrap(), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn push_pop() {
+        let list = List::new().push(1).push(2).push(3);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.head(), Some(&3));
+
+        let (elem, list) = list.pop().unwrap();
+        assert_eq!(elem, 3);
+        let (elem, list) = list.pop().unwrap();
+        assert_eq!(elem, 2);
+        let (elem, list) = list.pop().unwrap();
+        assert_eq!(elem, 1);
+        assert!(list.is_empty());
+        assert!(list.pop().is_none());
+
+        // Popping a shared node leaves the other list as it was
+        let shared = List::new().push(Rc::new(1)).push(Rc::new(2));
+        let (copy, list) = shared.clone().pop().unwrap();
+        assert_eq!(Rc::strong_count(&copy), 2);
+        assert_eq!(list, shared.tail());
+        drop(copy);
+
+        // The last holder of the node gets the element itself
+        let (elem, _) = shared.pop().unwrap();
+        assert_eq!(Rc::strong_count(&elem), 1);
+    }
+
+    #[test]
+    fn head_mut() {
+        let mut list = List::new().push(1).push(2);
+        let unique = list.head.as_deref().unwrap() as *const _;
+        *list.head_mut().unwrap() = 3;
+        // Not shared, so changed in place
+        assert_eq!(list.head.as_deref().unwrap() as *const _, unique);
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &1]);
+
+        let shared = list.clone();
+        *list.head_mut().unwrap() = 4;
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&4, &1]);
+        assert_eq!(shared.iter().collect::<Vec<_>>(), [&3, &1]);
+        // Only the head node was copied
+        assert_eq!(list.tail(), shared.tail());
+        assert!(RcFamily::ptr_eq(
+            list.head.as_ref().unwrap().next.as_ref().unwrap(),
+            shared.head.as_ref().unwrap().next.as_ref().unwrap()
+        ));
+
+        assert_eq!(List::<i32>::new().head_mut(), None);
+    }
+
+    #[test]
+    fn try_head_mut() {
+        // Not `Clone`, so only ever changed in place
+        #[derive(Debug, PartialEq)]
+        struct Elem(u32);
+
+        let mut list = List::new().push(Elem(1)).push(Elem(2));
+        list.try_head_mut().unwrap().0 = 3;
+        assert_eq!(list.head(), Some(&Elem(3)));
+
+        let shared = list.clone();
+        assert_eq!(list.try_head_mut(), None);
+        drop(shared);
+        assert_eq!(list.try_head_mut(), Some(&mut Elem(3)));
+
+        // Only the head node has to be unique
+        let mut list = list.tail().push(Elem(4));
+        assert_eq!(list.try_head_mut(), Some(&mut Elem(4)));
+        assert_eq!(List::<Elem>::new().try_head_mut(), None);
+    }
+
+    fn long_drop<P: RefCountedFamily>() {
+        // Long enough to overflow the stack if drop recursed
+        let mut shared = List::<i32, P>::default();
+        for i in 0..200_000 {
+            shared = shared.prepend(i);
+        }
+        let mut list = shared.tail();
+        for i in 0..200_000 {
+            list = list.prepend(i);
+        }
+
+        // Dropping the longer list stops where `shared` still holds on
+        drop(list);
+        assert_eq!(shared.head(), Some(&199_999));
+        assert_eq!(shared.iter().count(), 200_000);
+        assert_eq!(shared.len(), 200_000);
+        drop(shared);
+    }
+
+    #[test]
+    fn long_drop_rc() {
+        long_drop::<RcFamily>();
+    }
+
+    #[test]
+    fn long_drop_arc() {
+        long_drop::<ArcFamily>();
+    }
+
+    #[test]
+    fn long_drop_arc_concurrent() {
+        // Threads letting go of the same long list at once. If each of them
+        // saw it still shared, the last reference would go recursively and
+        // overflow the stack.
+        const THREADS: usize = 4;
+        for _ in 0..50 {
+            let shared: List<i32, ArcFamily> = (0..100_000).collect();
+            let lists = vec![shared; THREADS];
+            let barrier = Barrier::new(THREADS);
+            thread::scope(|s| {
+                for list in lists {
+                    let barrier = &barrier;
+                    s.spawn(move || {
+                        barrier.wait();
+                        drop(list);
+                    });
+                }
+            });
+        }
+    }
 }
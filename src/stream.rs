@@ -0,0 +1,370 @@
+use std::cell::{Cell, OnceCell};
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::persistent_stack::List;
+
+/// A lazy persistent list. Every cell is computed the first time someone
+/// looks at it, and the result is kept, so a cell shared between several
+/// streams is only ever computed once. Cells nobody looks at are never
+/// computed, which makes infinite streams fine as long as only a finite
+/// part of them is used.
+pub struct Stream<T> {
+    cell: Rc<Lazy<T>>,
+}
+
+struct Lazy<T> {
+    value: OnceCell<Option<Cons<T>>>,
+    /// Taken when the cell is forced
+    thunk: Cell<Option<Thunk<T>>>,
+}
+
+type Thunk<T> = Box<dyn FnOnce() -> Option<(T, Stream<T>)>>;
+
+struct Cons<T> {
+    head: T,
+    tail: Stream<T>,
+}
+
+impl<T> Lazy<T> {
+    fn force(&self) -> Option<&Cons<T>> {
+        self.value
+            .get_or_init(|| {
+                let thunk = self.thunk.take().expect(
+                    "stream cell forced while being computed, or after computing it panicked",
+                );
+                thunk().map(|(head, tail)| Cons { head, tail })
+            })
+            .as_ref()
+    }
+}
+
+impl<T> Drop for Lazy<T> {
+    fn drop(&mut self) {
+        // A long forced stream is a long chain of cells, unlink it one cell
+        // at a time instead of recursing
+        let mut next = self.value.take().flatten();
+        while let Some(cons) = next {
+            next = match Rc::try_unwrap(cons.tail.cell) {
+                Ok(mut lazy) => lazy.value.take().flatten(),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+impl<T> Stream<T> {
+    pub fn empty() -> Self {
+        Stream::from_value(None)
+    }
+
+    /// An already computed cell.
+    pub fn cons(head: T, tail: Stream<T>) -> Self {
+        Stream::from_value(Some(Cons { head, tail }))
+    }
+
+    /// A cell computed by `thunk` the first time it's looked at. `None`
+    /// ends the stream. If `thunk` panics it's gone, and every later look at
+    /// the cell panics too.
+    pub fn lazy(thunk: impl FnOnce() -> Option<(T, Stream<T>)> + 'static) -> Self {
+        Stream {
+            cell: Rc::new(Lazy {
+                value: OnceCell::new(),
+                thunk: Cell::new(Some(Box::new(thunk))),
+            }),
+        }
+    }
+
+    fn from_value(value: Option<Cons<T>>) -> Self {
+        Stream {
+            cell: Rc::new(Lazy {
+                value: OnceCell::from(value),
+                thunk: Cell::new(None),
+            }),
+        }
+    }
+
+    /// Forces the first cell.
+    pub fn is_empty(&self) -> bool {
+        self.cell.force().is_none()
+    }
+
+    /// Forces the first cell.
+    pub fn head(&self) -> Option<&T> {
+        self.cell.force().map(|cons| &cons.head)
+    }
+
+    /// Forces the first cell, but not the rest.
+    pub fn tail(&self) -> Stream<T> {
+        match self.cell.force() {
+            Some(cons) => cons.tail.clone(),
+            None => Stream::empty(),
+        }
+    }
+
+    /// The head and the tail, forcing only the first cell.
+    pub fn uncons(&self) -> Option<(&T, Stream<T>)> {
+        self.cell
+            .force()
+            .map(|cons| (&cons.head, cons.tail.clone()))
+    }
+
+    /// Forces each cell as the iterator gets to it.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: Some(self) }
+    }
+
+    /// Forces the whole stream, so it never returns for an infinite one.
+    pub fn to_list(&self) -> List<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: 'static> Stream<T> {
+    /// `seed`, `f(seed)`, `f(f(seed))` and so on, forever.
+    pub fn iterate(seed: T, f: impl Fn(&T) -> T + 'static) -> Self
+    where
+        T: Clone,
+    {
+        fn go<T: Clone + 'static>(elem: T, f: Rc<dyn Fn(&T) -> T>) -> (T, Stream<T>) {
+            let prev = elem.clone();
+            (elem, Stream::lazy(move || Some(go(f(&prev), f))))
+        }
+        let (head, tail) = go(seed, Rc::new(f));
+        Stream::cons(head, tail)
+    }
+
+    /// Calls `f` on the state for each element and the next state, until it
+    /// returns `None`.
+    pub fn unfold<S: 'static>(init: S, f: impl Fn(S) -> Option<(T, S)> + 'static) -> Self {
+        fn go<T: 'static, S: 'static>(state: S, f: Rc<dyn Fn(S) -> Option<(T, S)>>) -> Stream<T> {
+            Stream::lazy(move || {
+                let (elem, state) = f(state)?;
+                Some((elem, go(state, f)))
+            })
+        }
+        go(init, Rc::new(f))
+    }
+
+    /// The first `n` elements.
+    pub fn take(&self, n: usize) -> Stream<T>
+    where
+        T: Clone,
+    {
+        if n == 0 {
+            return Stream::empty();
+        }
+        let source = self.clone();
+        Stream::lazy(move || {
+            let (head, tail) = source.uncons()?;
+            Some((head.clone(), tail.take(n - 1)))
+        })
+    }
+
+    pub fn map<U: 'static>(&self, f: impl Fn(&T) -> U + 'static) -> Stream<U> {
+        fn go<T: 'static, U: 'static>(source: Stream<T>, f: Rc<dyn Fn(&T) -> U>) -> Stream<U> {
+            Stream::lazy(move || {
+                let (head, tail) = source.uncons()?;
+                Some((f(head), go(tail, f)))
+            })
+        }
+        go(self.clone(), Rc::new(f))
+    }
+
+    /// Forcing a cell of the result forces the source up to the next
+    /// element that passes, so that never returns if none is left in an
+    /// infinite stream.
+    pub fn filter(&self, pred: impl Fn(&T) -> bool + 'static) -> Stream<T>
+    where
+        T: Clone,
+    {
+        fn go<T: Clone + 'static>(source: Stream<T>, pred: Rc<dyn Fn(&T) -> bool>) -> Stream<T> {
+            Stream::lazy(move || {
+                let mut source = source;
+                loop {
+                    let (head, tail) = source.uncons()?;
+                    if pred(head) {
+                        return Some((head.clone(), go(tail, pred)));
+                    }
+                    source = tail;
+                }
+            })
+        }
+        go(self.clone(), Rc::new(pred))
+    }
+
+    /// Pairs of elements, as long as the shorter of the two.
+    pub fn zip<U: Clone + 'static>(&self, other: &Stream<U>) -> Stream<(T, U)>
+    where
+        T: Clone,
+    {
+        let (left, right) = (self.clone(), other.clone());
+        Stream::lazy(move || {
+            let (a, left) = left.uncons()?;
+            let (b, right) = right.uncons()?;
+            Some(((a.clone(), b.clone()), left.zip(&right)))
+        })
+    }
+}
+
+impl<T> Clone for Stream<T> {
+    fn clone(&self) -> Self {
+        Stream {
+            cell: self.cell.clone(),
+        }
+    }
+}
+
+impl<T> Default for Stream<T> {
+    fn default() -> Self {
+        Stream::empty()
+    }
+}
+
+impl<T: Debug> Debug for Stream<T> {
+    /// Only shows what has been computed so far, and `..` if there's more.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        let mut cell = &self.cell;
+        loop {
+            match cell.value.get() {
+                Some(Some(cons)) => {
+                    list.entry(&cons.head);
+                    cell = &cons.tail.cell;
+                }
+                Some(None) => return list.finish(),
+                None => return list.finish_non_exhaustive(),
+            }
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Stream<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Stream<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // A forced cell never changes again, so borrowing from it for as
+        // long as the first stream is borrowed is fine
+        let cons = self.next?.cell.force();
+        self.next = cons.map(|cons| &cons.tail);
+        cons.map(|cons| &cons.head)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Stream;
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    fn contents(stream: &Stream<u64>) -> Vec<u64> {
+        stream.iter().cloned().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let stream = Stream::cons(1, Stream::cons(2, Stream::empty()));
+        assert_eq!(stream.head(), Some(&1));
+        assert_eq!(stream.tail().head(), Some(&2));
+        assert!(stream.tail().tail().is_empty());
+        assert!(stream.tail().tail().tail().is_empty());
+
+        let (head, tail) = stream.uncons().unwrap();
+        assert_eq!(head, &1);
+        assert_eq!(contents(&Stream::cons(0, tail)), [0, 2]);
+        assert_eq!(contents(&stream), [1, 2]);
+
+        let list = stream.to_list();
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &2]);
+    }
+
+    #[test]
+    fn infinite() {
+        let naturals = Stream::iterate(0u64, |n| n + 1);
+        assert_eq!(contents(&naturals.take(5)), [0, 1, 2, 3, 4]);
+
+        let fib = Stream::unfold((0u64, 1u64), |(a, b)| Some((a, (b, a + b))));
+        assert_eq!(contents(&fib.take(10)), [0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
+
+        let even_squares = naturals.map(|n| n * n).filter(|n| n % 2 == 0);
+        assert_eq!(contents(&even_squares.take(4)), [0, 4, 16, 36]);
+
+        let pairs = naturals.zip(&fib.take(3));
+        let pairs: Vec<_> = pairs.iter().cloned().collect();
+        assert_eq!(pairs, [(0, 0), (1, 1), (2, 1)]);
+
+        let countdown = Stream::unfold(3u64, |n| n.checked_sub(1).map(|m| (n, m)));
+        assert_eq!(contents(&countdown), [3, 2, 1]);
+    }
+
+    #[test]
+    fn forced_once() {
+        let calls = Rc::new(Cell::new(0));
+        let counted = calls.clone();
+        let stream = Stream::unfold(0u64, move |n| {
+            counted.set(counted.get() + 1);
+            Some((n, n + 1))
+        });
+        assert_eq!(calls.get(), 0);
+
+        // Two versions sharing the same tail
+        let a = Stream::cons(100, stream.clone());
+        let b = Stream::cons(200, stream.clone());
+        assert_eq!(contents(&a.take(4)), [100, 0, 1, 2]);
+        assert_eq!(calls.get(), 3);
+        assert_eq!(contents(&b.take(6)), [200, 0, 1, 2, 3, 4]);
+        assert_eq!(calls.get(), 5);
+        assert_eq!(contents(&stream.take(5)), [0, 1, 2, 3, 4]);
+        assert_eq!(calls.get(), 5);
+    }
+
+    #[test]
+    fn debug() {
+        let stream = Stream::iterate(1u64, |n| n * 2);
+        assert_eq!(format!("{:?}", stream), "[1, ..]");
+        stream.iter().nth(3);
+        assert_eq!(format!("{:?}", stream), "[1, 2, 4, 8, ..]");
+        assert_eq!(format!("{:?}", stream.take(2).to_list()), "[1, 2]");
+
+        let finite = Stream::cons(1, Stream::empty());
+        assert_eq!(format!("{:?}", finite), "[1]");
+    }
+
+    #[test]
+    fn panicking_thunk() {
+        let stream: Stream<u64> = Stream::lazy(|| panic!("thunk failed"));
+        let first = catch_unwind(AssertUnwindSafe(|| stream.head().copied()));
+        assert!(first.is_err());
+
+        // The thunk is gone, so later forces can only say so
+        let again = catch_unwind(AssertUnwindSafe(|| stream.head().copied())).unwrap_err();
+        let message = again.downcast_ref::<String>().unwrap();
+        assert!(message.contains("after computing it panicked"));
+    }
+
+    #[test]
+    fn long_drop() {
+        // Long enough to overflow the stack if drop recursed
+        let stream = Stream::iterate(0u64, |n| n + 1);
+        assert_eq!(stream.iter().nth(200_000), Some(&200_000));
+        drop(stream);
+    }
+}
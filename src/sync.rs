@@ -0,0 +1,10 @@
+//! Collections that can be shared between threads without locks.
+
+mod atomic_list;
+mod epoch;
+mod mpsc_queue;
+mod treiber_stack;
+
+pub use atomic_list::AtomicList;
+pub use mpsc_queue::MpscQueue;
+pub use treiber_stack::TreiberStack;
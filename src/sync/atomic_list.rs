@@ -0,0 +1,202 @@
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use super::epoch;
+use crate::gat::ArcFamily;
+use crate::persistent_stack::List;
+
+/// A shared slot holding a `persistent_stack::List`, for publishing new
+/// versions of it to other threads.
+///
+/// Readers take a `snapshot`, which is just another handle on the list's
+/// `Arc` nodes: no locks, and nothing a writer does later changes it.
+/// Writers build the next version from the current one with `update`, and
+/// publish it with a compare-and-swap, retrying if someone else got there
+/// first. The slot points at a boxed list handle, and a replaced handle goes
+/// to the epoch collector, since a reader may be cloning it right then.
+pub struct AtomicList<T> {
+    /// Never null
+    head: AtomicPtr<List<T, ArcFamily>>,
+}
+
+unsafe impl<T: Send + Sync> Send for AtomicList<T> {}
+
+unsafe impl<T: Send + Sync> Sync for AtomicList<T> {}
+
+impl<T> AtomicList<T> {
+    pub fn new() -> Self {
+        Self::from(List::default())
+    }
+
+    /// The current version.
+    pub fn snapshot(&self) -> List<T, ArcFamily> {
+        let _guard = epoch::pin();
+        // Pinned, so the handle can't be freed while we clone it
+        unsafe { (*self.head.load(Ordering::Acquire)).clone() }
+    }
+}
+
+impl<T: Send + Sync + 'static> AtomicList<T> {
+    /// Replace the list with `f` of it and return the new version. `f` runs
+    /// again on the newer version whenever another writer got in first, so
+    /// it should have no side effects.
+    pub fn update(
+        &self,
+        mut f: impl FnMut(&List<T, ArcFamily>) -> List<T, ArcFamily>,
+    ) -> List<T, ArcFamily> {
+        let guard = epoch::pin();
+        let mut current = self.head.load(Ordering::Acquire);
+        loop {
+            let next = f(unsafe { &*current });
+            let published = next.clone();
+            let next = Box::into_raw(Box::new(next));
+            match self
+                .head
+                .compare_exchange(current, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    unsafe { guard.defer_destroy(current) };
+                    return published;
+                }
+                Err(newer) => {
+                    drop(unsafe { Box::from_raw(next) });
+                    current = newer;
+                }
+            }
+        }
+    }
+}
+
+impl<T> From<List<T, ArcFamily>> for AtomicList<T> {
+    fn from(list: List<T, ArcFamily>) -> Self {
+        AtomicList {
+            head: AtomicPtr::new(Box::into_raw(Box::new(list))),
+        }
+    }
+}
+
+impl<T> Drop for AtomicList<T> {
+    fn drop(&mut self) {
+        // Nobody can be in `snapshot` or `update` anymore
+        drop(unsafe { Box::from_raw(*self.head.get_mut()) });
+    }
+}
+
+impl<T> Default for AtomicList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug> Debug for AtomicList<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AtomicList").field(&self.snapshot()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AtomicList;
+    use crate::persistent_stack::List;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let list = AtomicList::new();
+        assert!(list.snapshot().is_empty());
+
+        let before = list.snapshot();
+        let published = list.update(|list| list.prepend(1).prepend(2));
+        assert_eq!(published.iter().collect::<Vec<_>>(), [&2, &1]);
+        assert_eq!(list.snapshot(), published);
+        // Old snapshots don't change
+        assert!(before.is_empty());
+
+        list.update(|list| list.tail());
+        assert_eq!(list.snapshot().iter().collect::<Vec<_>>(), [&1]);
+        assert_eq!(format!("{:?}", list), "AtomicList([1])");
+
+        let list = AtomicList::from((0..3).collect::<List<_, _>>());
+        assert_eq!(list.snapshot().len(), 3);
+    }
+
+    #[test]
+    fn stress() {
+        const WRITERS: usize = 4;
+        const READERS: usize = 4;
+        const PER_WRITER: usize = 2_000;
+
+        let list = AtomicList::new();
+        let done = AtomicBool::new(false);
+        thread::scope(|s| {
+            let writers: Vec<_> = (0..WRITERS)
+                .map(|w| {
+                    let list = &list;
+                    s.spawn(move || {
+                        for i in 0..PER_WRITER {
+                            list.update(|list| list.prepend((w, i)));
+                        }
+                    })
+                })
+                .collect();
+
+            for _ in 0..READERS {
+                s.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        let snapshot = list.snapshot();
+                        // Every snapshot is a whole version: each writer's
+                        // elements newest first and nothing missing
+                        let mut last = [None; WRITERS];
+                        for &(w, i) in snapshot.iter() {
+                            if let Some(newer) = last[w] {
+                                assert_eq!(i + 1, newer);
+                            }
+                            last[w] = Some(i);
+                        }
+                        assert!(last.iter().all(|&oldest| matches!(oldest, None | Some(0))));
+                        assert_eq!(snapshot.iter().count(), snapshot.len());
+                    }
+                });
+            }
+
+            for writer in writers {
+                writer.join().unwrap();
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+
+        let snapshot = list.snapshot();
+        assert_eq!(snapshot.len(), WRITERS * PER_WRITER);
+    }
+
+    #[test]
+    fn long_versions() {
+        // Every version shares a long tail, and replaced versions are freed
+        // by the collector while readers drop their snapshots of them. A
+        // drop that recursed anywhere in there would overflow the stack.
+        const LEN: i32 = 200_000;
+        let list = AtomicList::from((0..LEN).collect::<List<_, _>>());
+        let done = AtomicBool::new(false);
+        thread::scope(|s| {
+            let writer = s.spawn(|| {
+                for i in 0..1000 {
+                    list.update(|list| list.tail().prepend(-i));
+                }
+            });
+            for _ in 0..4 {
+                s.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        let snapshot = list.snapshot();
+                        assert_eq!(snapshot.len(), LEN as usize);
+                    }
+                });
+            }
+            writer.join().unwrap();
+            done.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(list.snapshot().head(), Some(&-999));
+        drop(list);
+    }
+}
@@ -0,0 +1,278 @@
+//! A small epoch-based reclamation scheme.
+//!
+//! A thread `pin`s itself before reading shared pointers and stays pinned
+//! until the returned `Guard` is dropped. Pinning records the global epoch
+//! the thread saw, and the global epoch can only move on once every pinned
+//! thread has seen the current one. Memory unlinked while the global epoch
+//! is `e` is handed to `Guard::defer_destroy` and only freed once the global
+//! epoch reaches `e + 2`: by then every thread that could have read the
+//! pointer has unpinned.
+
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Set in a participant's epoch while it is pinned
+const PINNED: usize = 1;
+/// The epoch counts above the pinned bit
+const STEP: usize = 2;
+/// How many pins a thread makes between attempts to advance and collect
+const COLLECT_EVERY: usize = 64;
+
+static EPOCH: AtomicUsize = AtomicUsize::new(0);
+/// Every participant ever registered. Only ever prepended to, and the
+/// participants are never freed, just reused once their thread exits.
+static PARTICIPANTS: AtomicPtr<Participant> = AtomicPtr::new(ptr::null_mut());
+/// Garbage left behind by threads that exited before it could be freed
+static ORPHANS: Mutex<Vec<Deferred>> = Mutex::new(Vec::new());
+
+struct Participant {
+    /// The epoch the thread pinned at, with `PINNED` set while pinned
+    epoch: AtomicUsize,
+    in_use: AtomicBool,
+    /// Fixed before the participant is published
+    next: *mut Participant,
+}
+
+/// Memory waiting for the epoch to move on before it can be freed.
+struct Deferred {
+    ptr: *mut (),
+    free: unsafe fn(*mut ()),
+    epoch: usize,
+}
+
+// `defer_destroy` makes the caller promise the memory can be freed from any
+// thread, which is all that can happen to it after it's orphaned.
+unsafe impl Send for Deferred {}
+
+struct Local {
+    participant: &'static Participant,
+    guards: Cell<usize>,
+    pins: Cell<usize>,
+    garbage: RefCell<Vec<Deferred>>,
+}
+
+thread_local! {
+    static LOCAL: Local = Local::register();
+}
+
+/// Keeps the current thread pinned. Pointers read from the lock-free
+/// structures stay valid for as long as it is alive.
+pub(crate) struct Guard {
+    _not_send: PhantomData<*mut ()>,
+}
+
+pub(crate) fn pin() -> Guard {
+    LOCAL.with(Local::pin);
+    Guard {
+        _not_send: PhantomData,
+    }
+}
+
+impl Guard {
+    /// Free `ptr` as a `Box<T>` once no pinned thread can still reach it.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `Box::into_raw`, must already be unreachable for
+    /// any thread that pins from now on, and dropping the `Box<T>` must be
+    /// fine on any thread at any later point.
+    pub(crate) unsafe fn defer_destroy<T>(&self, ptr: *mut T) {
+        unsafe fn free<T>(ptr: *mut ()) {
+            drop(Box::from_raw(ptr.cast::<T>()));
+        }
+        // Stamp it with the global epoch as of after the unlink. The epoch
+        // we pinned at can be a step behind, and a thread pinned since the
+        // last advance may still have read `ptr` before it was unlinked.
+        fence(Ordering::SeqCst);
+        let epoch = EPOCH.load(Ordering::Relaxed);
+        LOCAL.with(|local| {
+            local.garbage.borrow_mut().push(Deferred {
+                ptr: ptr.cast(),
+                free: free::<T>,
+                epoch,
+            });
+        });
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        LOCAL.with(Local::unpin);
+    }
+}
+
+impl Local {
+    fn register() -> Local {
+        // Take over the participant of a thread that has exited, if any
+        let mut cur = PARTICIPANTS.load(Ordering::Acquire);
+        while let Some(participant) = unsafe { cur.as_ref() } {
+            if participant
+                .in_use
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                return Local::new(participant);
+            }
+            cur = participant.next;
+        }
+
+        let participant = Box::into_raw(Box::new(Participant {
+            epoch: AtomicUsize::new(0),
+            in_use: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = PARTICIPANTS.load(Ordering::Acquire);
+        loop {
+            unsafe { (*participant).next = head };
+            match PARTICIPANTS.compare_exchange_weak(
+                head,
+                participant,
+                Ordering::Release,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(new_head) => head = new_head,
+            }
+        }
+        Local::new(unsafe { &*participant })
+    }
+
+    fn new(participant: &'static Participant) -> Local {
+        Local {
+            participant,
+            guards: Cell::new(0),
+            pins: Cell::new(0),
+            garbage: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn pin(&self) {
+        let guards = self.guards.get();
+        self.guards.set(guards + 1);
+        if guards > 0 {
+            // Already pinned further up the stack
+            return;
+        }
+
+        let epoch = EPOCH.load(Ordering::Relaxed);
+        self.participant
+            .epoch
+            .store(epoch | PINNED, Ordering::Relaxed);
+        // The pin has to be visible before we read any shared pointer
+        fence(Ordering::SeqCst);
+
+        let pins = self.pins.get().wrapping_add(1);
+        self.pins.set(pins);
+        if pins.is_multiple_of(COLLECT_EVERY) {
+            try_advance();
+            self.collect();
+        }
+    }
+
+    fn unpin(&self) {
+        let guards = self.guards.get() - 1;
+        self.guards.set(guards);
+        if guards == 0 {
+            let epoch = self.participant.epoch.load(Ordering::Relaxed);
+            // Everything we read while pinned happens before the unpin
+            self.participant
+                .epoch
+                .store(epoch & !PINNED, Ordering::Release);
+        }
+    }
+
+    /// Free whatever garbage is old enough, ours and any orphaned.
+    fn collect(&self) {
+        let epoch = EPOCH.load(Ordering::Acquire);
+        let ready = |deferred: &Deferred| epoch.wrapping_sub(deferred.epoch) >= 2 * STEP;
+
+        let mut freeing: Vec<_> = self
+            .garbage
+            .borrow_mut()
+            .extract_if(.., |deferred| ready(deferred))
+            .collect();
+        // Never block on the orphans, someone else will get to them
+        if let Ok(mut orphans) = ORPHANS.try_lock() {
+            freeing.extend(orphans.extract_if(.., |deferred| ready(deferred)));
+        }
+        for deferred in freeing {
+            unsafe { (deferred.free)(deferred.ptr) };
+        }
+    }
+}
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        let garbage = std::mem::take(self.garbage.get_mut());
+        if !garbage.is_empty() {
+            ORPHANS
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .extend(garbage);
+        }
+        self.participant.epoch.store(0, Ordering::Release);
+        self.participant.in_use.store(false, Ordering::Release);
+    }
+}
+
+/// Move the global epoch on if every pinned thread has caught up with it.
+fn try_advance() {
+    let epoch = EPOCH.load(Ordering::Relaxed);
+    fence(Ordering::SeqCst);
+
+    let mut cur = PARTICIPANTS.load(Ordering::Acquire);
+    while let Some(participant) = unsafe { cur.as_ref() } {
+        let theirs = participant.epoch.load(Ordering::Relaxed);
+        if theirs & PINNED != 0 && theirs & !PINNED != epoch {
+            return;
+        }
+        cur = participant.next;
+    }
+    // Pair with the unpin stores so their reads are done before we free
+    fence(Ordering::Acquire);
+    let _ = EPOCH.compare_exchange(
+        epoch,
+        epoch.wrapping_add(STEP),
+        Ordering::Release,
+        Ordering::Relaxed,
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn deferred_garbage_is_freed() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counted;
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        {
+            let guard = pin();
+            // Nested pins don't unpin early
+            drop(pin());
+            unsafe { guard.defer_destroy(Box::into_raw(Box::new(Counted))) };
+        }
+        assert_eq!(DROPS.load(Ordering::Relaxed), 0);
+
+        // Other tests may hold pins for a moment, so give it a while
+        for _ in 0..1_000_000 {
+            drop(pin());
+            if DROPS.load(Ordering::Relaxed) == 1 {
+                return;
+            }
+        }
+        panic!("deferred garbage was never freed");
+    }
+}
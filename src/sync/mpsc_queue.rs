@@ -0,0 +1,193 @@
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+/// A lock-free queue with any number of producers and a single consumer.
+///
+/// It keeps the head/tail layout of `an_ok_unsafe_queue::List`, except that
+/// producers add at `head` with a single `swap`, and the list always holds
+/// at least a stub node whose element has already been taken. The consumer
+/// only ever touches `tail`, so it needs no atomics beyond reading `next`.
+///
+/// A producer links its node in two steps, so for a moment after the `swap`
+/// the node isn't reachable from `tail` yet. `pop` returns `None` in that
+/// window too, and the element shows up on a later `pop`.
+pub struct MpscQueue<T> {
+    /// The newest node, where producers push
+    head: AtomicPtr<Node<T>>,
+    /// The stub in front of the oldest element, only touched by the consumer
+    tail: UnsafeCell<*mut Node<T>>,
+    /// Set while someone is in `pop`, to catch a second consumer
+    consuming: AtomicBool,
+}
+
+struct Node<T> {
+    next: AtomicPtr<Node<T>>,
+    /// `None` for the stub
+    elem: Option<T>,
+}
+
+unsafe impl<T: Send> Send for MpscQueue<T> {}
+
+unsafe impl<T: Send> Sync for MpscQueue<T> {}
+
+impl<T> Node<T> {
+    fn new(elem: Option<T>) -> *mut Node<T> {
+        Box::into_raw(Box::new(Node {
+            next: AtomicPtr::new(ptr::null_mut()),
+            elem,
+        }))
+    }
+}
+
+impl<T> MpscQueue<T> {
+    pub fn new() -> Self {
+        let stub = Node::new(None);
+        MpscQueue {
+            head: AtomicPtr::new(stub),
+            tail: UnsafeCell::new(stub),
+            consuming: AtomicBool::new(false),
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Node::new(Some(elem));
+        // Claim the head first, then link the old head to us. The old head
+        // can't be freed in between, the consumer never gets past it while
+        // its `next` is still null.
+        let prev = self.head.swap(node, Ordering::AcqRel);
+        unsafe { (*prev).next.store(node, Ordering::Release) };
+    }
+
+    /// Take the oldest element.
+    ///
+    /// # Panics
+    ///
+    /// If another thread is popping at the same time. Taking turns is fine.
+    pub fn pop(&self) -> Option<T> {
+        assert!(
+            !self.consuming.swap(true, Ordering::Acquire),
+            "MpscQueue can only have one consumer at a time"
+        );
+        let elem = unsafe {
+            let tail = *self.tail.get();
+            let next = (*tail).next.load(Ordering::Acquire);
+            if next.is_null() {
+                None
+            } else {
+                // `next` becomes the stub and the old stub goes
+                *self.tail.get() = next;
+                drop(Box::from_raw(tail));
+                (*next).elem.take()
+            }
+        };
+        self.consuming.store(false, Ordering::Release);
+        elem
+    }
+}
+
+impl<T> Drop for MpscQueue<T> {
+    fn drop(&mut self) {
+        let mut cur = *self.tail.get_mut();
+        while !cur.is_null() {
+            let node = unsafe { Box::from_raw(cur) };
+            cur = node.next.load(Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> Default for MpscQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for MpscQueue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpscQueue").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MpscQueue;
+    use std::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let queue = MpscQueue::new();
+        assert_eq!(queue.pop(), None);
+
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+
+        queue.push(4);
+        queue.push(5);
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.pop(), None);
+
+        // Still works once it has been emptied
+        queue.push(6);
+        assert_eq!(queue.pop(), Some(6));
+    }
+
+    #[test]
+    fn drop_remaining() {
+        let rc = Rc::new(());
+        let queue = MpscQueue::new();
+        for _ in 0..10 {
+            queue.push(rc.clone());
+        }
+        drop(queue.pop());
+        assert_eq!(Rc::strong_count(&rc), 10);
+        drop(queue);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn stress() {
+        const PRODUCERS: usize = 8;
+        const PER_PRODUCER: usize = 10_000;
+
+        let queue = MpscQueue::new();
+        let received = thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                let queue = &queue;
+                s.spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        queue.push((p, i));
+                    }
+                });
+            }
+
+            let consumer = s.spawn(|| {
+                let mut next = [0; PRODUCERS];
+                let mut received = 0;
+                while received < PRODUCERS * PER_PRODUCER {
+                    match queue.pop() {
+                        Some((p, i)) => {
+                            // Each producer's elements come out in order
+                            assert_eq!(i, next[p]);
+                            next[p] += 1;
+                            received += 1;
+                        }
+                        None => thread::yield_now(),
+                    }
+                }
+                received
+            });
+            consumer.join().unwrap()
+        });
+
+        assert_eq!(received, PRODUCERS * PER_PRODUCER);
+        assert_eq!(queue.pop(), None);
+    }
+}
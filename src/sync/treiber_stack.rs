@@ -0,0 +1,184 @@
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use super::epoch;
+
+/// A lock-free stack that any number of threads can push to and pop from.
+///
+/// Popped nodes aren't freed straight away, since another thread may be
+/// looking at them in the middle of its own `pop`. They go to the epoch
+/// collector instead, which also keeps a freed node's address from being
+/// reused as the head while someone still expects the old one (ABA).
+pub struct TreiberStack<T> {
+    head: AtomicPtr<Node<T>>,
+    _p: PhantomData<T>,
+}
+
+struct Node<T> {
+    /// Moved out by whoever pops the node, so never dropped with it
+    elem: ManuallyDrop<T>,
+    next: *mut Node<T>,
+}
+
+unsafe impl<T: Send> Send for TreiberStack<T> {}
+
+unsafe impl<T: Send> Sync for TreiberStack<T> {}
+
+impl<T> TreiberStack<T> {
+    pub const fn new() -> Self {
+        TreiberStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomData,
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Box::into_raw(Box::new(Node {
+            elem: ManuallyDrop::new(elem),
+            next: ptr::null_mut(),
+        }));
+        // Never dereferences a shared node, so no need to pin
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*node).next = head };
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(new_head) => head = new_head,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            // Pinned, so `head` can't be freed under us even if it's popped
+            let node = unsafe { head.as_ref() }?;
+            match self.head.compare_exchange_weak(
+                head,
+                node.next,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => unsafe {
+                    let elem = ManuallyDrop::into_inner(ptr::read(&node.elem));
+                    guard.defer_destroy(head);
+                    return Some(elem);
+                },
+                Err(new_head) => head = new_head,
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T> Drop for TreiberStack<T> {
+    fn drop(&mut self) {
+        // No one else can see the stack anymore, so free nodes right away
+        let mut cur = *self.head.get_mut();
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            cur = node.next;
+            unsafe { ManuallyDrop::drop(&mut node.elem) };
+        }
+    }
+}
+
+impl<T> Default for TreiberStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for TreiberStack<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TreiberStack")
+            .field("is_empty", &self.is_empty())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TreiberStack;
+    use std::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let stack = TreiberStack::new();
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    fn drop_remaining() {
+        let rc = Rc::new(());
+        let stack = TreiberStack::new();
+        for _ in 0..10 {
+            stack.push(rc.clone());
+        }
+        drop(stack.pop());
+        assert_eq!(Rc::strong_count(&rc), 10);
+        drop(stack);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn stress() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 10_000;
+
+        let stack = TreiberStack::new();
+        let mut popped: Vec<usize> = thread::scope(|s| {
+            let workers: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let stack = &stack;
+                    s.spawn(move || {
+                        let mut popped = Vec::new();
+                        for i in 0..PER_THREAD {
+                            stack.push(t * PER_THREAD + i);
+                            // Pop about half as often as we push
+                            if i % 2 == 0 {
+                                popped.extend(stack.pop());
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().unwrap())
+                .collect()
+        });
+        while let Some(elem) = stack.pop() {
+            popped.push(elem);
+        }
+
+        // Every element came out exactly once
+        popped.sort_unstable();
+        assert_eq!(popped, (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+    }
+}
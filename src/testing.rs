@@ -0,0 +1,50 @@
+//! Helpers shared by the randomized tests.
+
+/// SplitMix64, small and good enough to drive the tests reproducibly.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+/// Exercise a persistent structure `S` against a model `M` of what it holds.
+///
+/// Every step picks a random version made so far, structure and model
+/// together, and `step` turns a copy of it into a new version, given the
+/// step number to use as a fresh element. `check` compares each new version
+/// with its model, and once a run is over every version is compared again,
+/// so an edit that leaks into a version it shares nodes with gets caught.
+pub(crate) fn check_versions<S: Clone, M: Clone>(
+    steps: u32,
+    empty: (S, M),
+    mut step: impl FnMut(&mut Rng, u32, S, M) -> (S, M),
+    check: impl Fn(&S, &M),
+) {
+    for seed in 0..20 {
+        let mut rng = Rng::new(seed);
+        let mut versions = vec![empty.clone()];
+        for i in 0..steps {
+            let (structure, model) = versions[rng.below(versions.len() as u64) as usize].clone();
+            let (structure, model) = step(&mut rng, i, structure, model);
+            check(&structure, &model);
+            versions.push((structure, model));
+        }
+        for (structure, model) in &versions {
+            check(structure, model);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+
+use crate::gat::{RcFamily, RefCountedFamily};
+use crate::persistent_stack::List;
+
+/// A persistent cursor into a sequence, the immutable counterpart of
+/// `a_production_linked_list::CursorMut`.
+///
+/// It is two `persistent_stack::List`s: everything before the cursor,
+/// nearest first, and the focus followed by everything after it. Every
+/// operation is O(1) and returns a new zipper that shares the rest of both
+/// lists with `self`. The cursor can sit one past the last element, where
+/// there is no focus and `insert` appends.
+///
+/// Moving copies the element that moves from one list to the other, hence
+/// `T: Clone` there.
+pub struct Zipper<T, P: RefCountedFamily = RcFamily> {
+    /// Reversed, the element right before the focus first
+    left: List<T, P>,
+    /// The focus first
+    right: List<T, P>,
+}
+
+impl<T> Zipper<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, P: RefCountedFamily> Zipper<T, P> {
+    /// A zipper focused on the first element of `list`.
+    pub fn from_list(list: List<T, P>) -> Self {
+        Zipper {
+            left: List::default(),
+            right: list,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.left.len() + self.right.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many elements come before the focus.
+    pub fn index(&self) -> usize {
+        self.left.len()
+    }
+
+    /// `None` past the last element.
+    pub fn focus(&self) -> Option<&T> {
+        self.right.head()
+    }
+
+    /// Insert `elem` before the focus and focus on it.
+    pub fn insert(&self, elem: T) -> Self {
+        Zipper {
+            left: self.left.clone(),
+            right: self.right.prepend(elem),
+        }
+    }
+
+    /// The focus and the zipper without it, focused on the element after.
+    pub fn delete(&self) -> Option<(&T, Self)> {
+        let elem = self.right.head()?;
+        let rest = Zipper {
+            left: self.left.clone(),
+            right: self.right.tail(),
+        };
+        Some((elem, rest))
+    }
+
+    /// The zipper with the focus swapped for `elem`, `None` if there's no
+    /// focus.
+    pub fn replace(&self, elem: T) -> Option<Self> {
+        self.right.head()?;
+        Some(Zipper {
+            left: self.left.clone(),
+            right: self.right.tail().prepend(elem),
+        })
+    }
+}
+
+impl<T: Clone, P: RefCountedFamily> Zipper<T, P> {
+    /// Focus on the previous element, `None` at the start.
+    pub fn move_left(&self) -> Option<Self> {
+        let elem = self.left.head()?;
+        Some(Zipper {
+            left: self.left.tail(),
+            right: self.right.prepend(elem.clone()),
+        })
+    }
+
+    /// Focus on the next element, or past the last one. `None` if already
+    /// past it.
+    pub fn move_right(&self) -> Option<Self> {
+        let elem = self.right.head()?;
+        Some(Zipper {
+            left: self.left.prepend(elem.clone()),
+            right: self.right.tail(),
+        })
+    }
+
+    /// The whole sequence. O(index), the part after the focus is shared.
+    pub fn to_list(&self) -> List<T, P> {
+        self.left
+            .iter()
+            .fold(self.right.clone(), |list, elem| list.prepend(elem.clone()))
+    }
+}
+
+impl<T, P: RefCountedFamily> Clone for Zipper<T, P> {
+    fn clone(&self) -> Self {
+        Zipper {
+            left: self.left.clone(),
+            right: self.right.clone(),
+        }
+    }
+}
+
+impl<T, P: RefCountedFamily> Default for Zipper<T, P> {
+    fn default() -> Self {
+        Zipper {
+            left: List::default(),
+            right: List::default(),
+        }
+    }
+}
+
+impl<T: Debug, P: RefCountedFamily> Debug for Zipper<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut before: Vec<&T> = self.left.iter().collect();
+        before.reverse();
+        f.debug_struct("Zipper")
+            .field("before", &before)
+            .field("after", &self.right)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Zipper;
+    use crate::persistent_stack::List;
+    use crate::testing::check_versions;
+
+    #[test]
+    fn basics() {
+        let zipper = Zipper::new();
+        assert_eq!(zipper.focus(), None);
+        assert!(zipper.move_left().is_none());
+        assert!(zipper.move_right().is_none());
+        assert!(zipper.delete().is_none());
+        assert!(zipper.replace(1).is_none());
+
+        // Inserting past the end appends
+        let zipper = zipper.insert(3).insert(2).insert(1);
+        assert!(zipper.to_list().iter().eq(&[1, 2, 3]));
+        assert_eq!(zipper.focus(), Some(&1));
+
+        let moved = zipper.move_right().unwrap().move_right().unwrap();
+        assert_eq!(moved.index(), 2);
+        assert_eq!(moved.focus(), Some(&3));
+        let end = moved.move_right().unwrap();
+        assert_eq!(end.focus(), None);
+        assert!(end.move_right().is_none());
+        assert!(end.insert(4).to_list().iter().eq(&[1, 2, 3, 4]));
+
+        let edited = moved.replace(30).unwrap().move_left().unwrap();
+        assert_eq!(edited.focus(), Some(&2));
+        let (elem, edited) = edited.delete().unwrap();
+        assert_eq!(elem, &2);
+        assert_eq!(edited.focus(), Some(&30));
+        assert!(edited.to_list().iter().eq(&[1, 30]));
+
+        // Old versions are untouched
+        assert!(zipper.to_list().iter().eq(&[1, 2, 3]));
+        assert!(moved.to_list().iter().eq(&[1, 2, 3]));
+        assert_eq!(moved.focus(), Some(&3));
+    }
+
+    #[test]
+    fn from_list() {
+        let list: List<u32> = (0..5).collect();
+        let zipper = Zipper::from_list(list.clone());
+        assert_eq!(zipper.index(), 0);
+        assert_eq!(zipper.focus(), Some(&0));
+        assert_eq!(zipper.to_list(), list);
+        assert_eq!(
+            format!("{:?}", zipper.move_right().unwrap()),
+            "Zipper { before: [0], after: [1, 2, 3, 4] }"
+        );
+    }
+
+    #[test]
+    fn versions() {
+        // The model is the sequence and the index the cursor should be at
+        check_versions(
+            300,
+            (Zipper::new(), (Vec::new(), 0)),
+            |rng, i, zipper, (mut model, mut at)| {
+                let next = match rng.below(5) {
+                    0 => {
+                        model.insert(at, i);
+                        Some(zipper.insert(i))
+                    }
+                    1 => zipper.move_left().inspect(|_| at -= 1),
+                    2 => zipper.move_right().inspect(|_| at += 1),
+                    3 => zipper.delete().map(|(elem, rest)| {
+                        assert_eq!(*elem, model.remove(at));
+                        rest
+                    }),
+                    _ => zipper.replace(i).inspect(|_| model[at] = i),
+                };
+                (next.unwrap_or(zipper), (model, at))
+            },
+            |zipper, (model, at)| {
+                assert_eq!(zipper.len(), model.len());
+                assert_eq!(zipper.index(), *at);
+                assert_eq!(zipper.focus(), model.get(*at));
+                assert!(zipper.to_list().iter().eq(model));
+            },
+        );
+    }
+}